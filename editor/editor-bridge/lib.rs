@@ -1,4 +1,10 @@
 use fframes_editor_controller::{prelude::*, setup_wasm_editor};
-use hello_world_example::upvotes_video::UpvotesVideo;
+use hello_world_example::{format::Portrait, story::Story, upvotes_video::UpvotesVideo};
 
-setup_wasm_editor!(UpvotesVideo, {});
+// the preview plays the media as it is, the trimming, the mixes and the ducking are applied by the
+// render command. The mixer still brings the pads of the manifest so the scenes are timed alike.
+setup_wasm_editor!(UpvotesVideo, {
+    story: Story::bundled(),
+    format: std::marker::PhantomData::<Portrait>,
+    mixer: Story::bundled().mixer()
+});
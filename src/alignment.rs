@@ -0,0 +1,234 @@
+use fframes::serde::Deserialize;
+use std::fmt::{Formatter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+struct WordTimestamp {
+    word: String,
+    timestamp: f32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+struct Segment {
+    whole_word_timestamps: Vec<WordTimestamp>,
+    #[serde(default)]
+    alt_start_timestamps: Vec<f32>,
+}
+
+/// Output of `stable-ts` (whisper with stabilized word timestamps), only the parts needed to
+/// build the subtitles.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Alignment {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedWord {
+    pub word: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Debug)]
+pub enum AlignmentError {
+    Io(PathBuf, std::io::Error),
+    Json(PathBuf, serde_json::Error),
+}
+
+impl std::fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlignmentError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            AlignmentError::Json(path, e) => write!(f, "invalid alignment {}: {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for AlignmentError {}
+
+impl Alignment {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, AlignmentError> {
+        let path = path.as_ref();
+        let source =
+            std::fs::read_to_string(path).map_err(|e| AlignmentError::Io(path.into(), e))?;
+
+        serde_json::from_str(&source).map_err(|e| AlignmentError::Json(path.into(), e))
+    }
+
+    /// Every word starts where the previous one ended, the first one at the start of the speech.
+    pub fn words(&self) -> Vec<TimedWord> {
+        let speech_start = self
+            .segments
+            .first()
+            .and_then(|segment| segment.alt_start_timestamps.first())
+            .copied()
+            .unwrap_or(0.);
+
+        let mut start = speech_start;
+        self.segments
+            .iter()
+            .flat_map(|segment| &segment.whole_word_timestamps)
+            .map(|word| {
+                let timed_word = TimedWord {
+                    word: word.word.clone(),
+                    start,
+                    end: word.timestamp,
+                };
+
+                start = word.timestamp;
+                timed_word
+            })
+            .collect()
+    }
+}
+
+/// Splits the words into sentences on the words ending with a dot. Words left after the last dot
+/// make up the final sentence.
+pub fn sentences(words: &[TimedWord]) -> Vec<&[TimedWord]> {
+    words
+        .split_inclusive(|word| word.word.ends_with('.'))
+        .collect()
+}
+
+/// `hh:mm:ss.ttt` timestamp of the seconds, negative ones are the start.
+pub(crate) fn vtt_timestamp(seconds: f32) -> String {
+    let millis = (seconds.max(0.) * 1000.).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn vtt<'a>(cues: impl Iterator<Item = (f32, f32, &'a str)>) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+
+    for (start, end, text) in cues {
+        // writing into a string never fails
+        let _ = write!(
+            vtt,
+            "\n{} --> {}\n{text}\n",
+            vtt_timestamp(start),
+            vtt_timestamp(end)
+        );
+    }
+
+    vtt
+}
+
+/// Sentence level subtitles, the `.vtt` sidecar of the narration.
+pub fn sentences_vtt(words: &[TimedWord]) -> String {
+    let sentences = sentences(words)
+        .into_iter()
+        .map(|sentence| {
+            let text = sentence.iter().map(|w| w.word.as_str()).collect::<String>();
+            (sentence[0].start, sentence[sentence.len() - 1].end, text)
+        })
+        .collect::<Vec<_>>();
+
+    vtt(sentences
+        .iter()
+        .map(|(start, end, text)| (*start, *end, text.as_str())))
+}
+
+/// Word level subtitles, the `.word.vtt` sidecar driving the karaoke highlight.
+pub fn words_vtt(words: &[TimedWord]) -> String {
+    vtt(words.iter().map(|w| (w.start, w.end, w.word.trim())))
+}
+
+/// Writes `<audio>.vtt` and `<audio>.word.vtt` next to the narration file.
+pub fn write_sidecars(
+    alignment: &Alignment,
+    audio_file: impl AsRef<Path>,
+) -> Result<(PathBuf, PathBuf), AlignmentError> {
+    let audio_file = audio_file.as_ref().as_os_str();
+    let words = alignment.words();
+
+    let mut sentences_path = audio_file.to_owned();
+    sentences_path.push(".vtt");
+    let mut words_path = audio_file.to_owned();
+    words_path.push(".word.vtt");
+
+    let (sentences_path, words_path) = (PathBuf::from(sentences_path), PathBuf::from(words_path));
+    std::fs::write(&sentences_path, sentences_vtt(&words))
+        .map_err(|e| AlignmentError::Io(sentences_path.clone(), e))?;
+    std::fs::write(&words_path, words_vtt(&words))
+        .map_err(|e| AlignmentError::Io(words_path.clone(), e))?;
+
+    Ok((sentences_path, words_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alignment() -> Alignment {
+        serde_json::from_str(
+            r#"{"segments": [
+                {
+                    "whole_word_timestamps": [
+                        {"word": " Hello", "timestamp": 0.8},
+                        {"word": " there.", "timestamp": 1.25}
+                    ],
+                    "alt_start_timestamps": [0.5, 0.6]
+                },
+                {
+                    "whole_word_timestamps": [
+                        {"word": " Bye", "timestamp": 3661.5}
+                    ]
+                }
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn chains_the_words_from_the_start_of_the_speech() {
+        assert_eq!(
+            alignment()
+                .words()
+                .iter()
+                .map(|word| (word.word.as_str(), word.start, word.end))
+                .collect::<Vec<_>>(),
+            vec![
+                (" Hello", 0.5, 0.8),
+                (" there.", 0.8, 1.25),
+                (" Bye", 1.25, 3661.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_timestamps_with_hours_and_millis() {
+        assert_eq!(vtt_timestamp(0.), "00:00:00.000");
+        assert_eq!(vtt_timestamp(3661.5), "01:01:01.500");
+        assert_eq!(vtt_timestamp(-0.2), "00:00:00.000");
+    }
+
+    #[test]
+    fn writes_a_cue_per_sentence() {
+        assert_eq!(
+            sentences_vtt(&alignment().words()),
+            "WEBVTT\n\
+             \n00:00:00.500 --> 00:00:01.250\n Hello there.\n\
+             \n00:00:01.250 --> 01:01:01.500\n Bye\n"
+        );
+    }
+
+    #[test]
+    fn writes_a_cue_per_trimmed_word() {
+        assert_eq!(
+            words_vtt(&alignment().words()),
+            "WEBVTT\n\
+             \n00:00:00.500 --> 00:00:00.800\nHello\n\
+             \n00:00:00.800 --> 00:00:01.250\nthere.\n\
+             \n00:00:01.250 --> 01:01:01.500\nBye\n"
+        );
+    }
+}
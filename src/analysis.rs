@@ -0,0 +1,126 @@
+use crate::loudness;
+use crate::mixing::{source_stamp, MixingError, DERIVED_DIR};
+use crate::silence::{self, Silence};
+use fframes::serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const CACHE_FILE: &str = "analysis.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+struct Measurement<T> {
+    size: u64,
+    modified: u64,
+    result: T,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(crate = "fframes::serde", default)]
+struct Measurements {
+    /// Integrated loudness, `None` for silent files.
+    loudness: BTreeMap<String, Measurement<Option<f32>>>,
+    /// Speech bounds, `None` for silent files.
+    speech: BTreeMap<String, Measurement<Option<(f32, f32)>>>,
+}
+
+/// Loudness and speech bounds of the media files, kept in the derived directory so repeated
+/// renders only measure the files that changed.
+#[derive(Debug)]
+pub struct AnalysisCache {
+    path: PathBuf,
+    measurements: Measurements,
+}
+
+/// Result of the file cached under the key, measured again once the file changes.
+fn cached<T: Clone>(
+    measurements: &mut BTreeMap<String, Measurement<T>>,
+    path: &Path,
+    key: String,
+    measure: impl FnOnce() -> Result<T, MixingError>,
+) -> Result<T, MixingError> {
+    let (size, modified) = source_stamp(path)?;
+
+    if let Some(measurement) = measurements.get(&key) {
+        if measurement.size == size && measurement.modified == modified {
+            return Ok(measurement.result.clone());
+        }
+    }
+
+    let result = measure()?;
+    measurements.insert(
+        key,
+        Measurement {
+            size,
+            modified,
+            result: result.clone(),
+        },
+    );
+
+    Ok(result)
+}
+
+impl AnalysisCache {
+    /// Starts from scratch when the cache is missing or can not be read.
+    pub fn load(media_dir: &Path) -> Self {
+        let path = media_dir.join(DERIVED_DIR).join(CACHE_FILE);
+        let measurements = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|cache| serde_json::from_str(&cache).ok())
+            .unwrap_or_default();
+
+        Self { path, measurements }
+    }
+
+    pub fn save(&self) -> Result<(), MixingError> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| MixingError::Io(dir.to_owned(), e))?;
+        }
+
+        let cache = serde_json::to_string_pretty(&self.measurements)
+            .map_err(|e| MixingError::Tool(self.path.clone(), e.to_string()))?;
+
+        std::fs::write(&self.path, cache).map_err(|e| MixingError::Io(self.path.clone(), e))
+    }
+
+    /// Loudness of the seconds of the file of the media directory given by `window`, measured
+    /// again only once the file or the window changes.
+    pub fn integrated(
+        &mut self,
+        media_dir: &Path,
+        file: &str,
+        window: (f32, Option<f32>),
+    ) -> Result<Option<f32>, MixingError> {
+        let path = media_dir.join(file);
+        let key = match window {
+            (start, None) if start <= 0. => file.to_owned(),
+            (start, end) => format!(
+                "{file}@{start:.3}-{}",
+                end.map(|end| format!("{end:.3}")).unwrap_or_default()
+            ),
+        };
+
+        cached(&mut self.measurements.loudness, &path, key, || {
+            loudness::measure(&path, window)
+        })
+    }
+
+    /// Speech bounds of the file of the media directory, found again only once the file or the
+    /// `[silence]` threshold, minimal duration or lead change.
+    pub fn speech_bounds(
+        &mut self,
+        media_dir: &Path,
+        file: &str,
+        silence: &Silence,
+    ) -> Result<Option<(f32, f32)>, MixingError> {
+        let path = media_dir.join(file);
+        let key = format!(
+            "{file}@{:.1}dB/{:.3}/{:.3}",
+            silence.threshold, silence.min_duration, silence.lead
+        );
+
+        cached(&mut self.measurements.speech, &path, key, || {
+            silence::speech_bounds(&path, silence)
+        })
+    }
+}
@@ -0,0 +1,160 @@
+use crate::emoji;
+use crate::format::Format;
+use crate::mixing::Mix;
+use crate::timing::SceneTiming;
+use crate::upvotes_video::UpvotesVideo;
+use fframes::Scene;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Audio,
+    Image,
+    Subtitles,
+    Font,
+}
+
+/// A file from the media directory that is requested while rendering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub kind: AssetKind,
+    pub file: String,
+    /// Processing of audio files ahead of the render.
+    pub mix: Mix,
+}
+
+impl Asset {
+    pub fn audio(file: impl Into<String>) -> Self {
+        Self::track(file, Mix::default())
+    }
+
+    pub fn track(file: impl Into<String>, mix: Mix) -> Self {
+        Self {
+            kind: AssetKind::Audio,
+            file: file.into(),
+            mix,
+        }
+    }
+
+    pub fn image(file: impl Into<String>) -> Self {
+        Self {
+            kind: AssetKind::Image,
+            file: file.into(),
+            mix: Mix::default(),
+        }
+    }
+
+    pub fn subtitles(file: impl Into<String>) -> Self {
+        Self {
+            kind: AssetKind::Subtitles,
+            file: file.into(),
+            mix: Mix::default(),
+        }
+    }
+
+    pub fn font(file: impl Into<String>) -> Self {
+        Self {
+            kind: AssetKind::Font,
+            file: file.into(),
+            mix: Mix::default(),
+        }
+    }
+}
+
+/// Lists every asset the scene's `audio_map` and `render_frame` will ask for, so missing media
+/// is reported before the render starts instead of in the middle of it.
+pub trait SceneAssets {
+    fn assets(&self) -> Vec<Asset>;
+}
+
+pub trait StoryScene: Scene + SceneAssets + SceneTiming {}
+
+impl<T: Scene + SceneAssets + SceneTiming> StoryScene for T {}
+
+#[derive(Debug)]
+pub struct MissingAsset {
+    /// Index of the scene requesting the asset, `None` for the video level ones
+    pub scene_index: Option<usize>,
+    pub asset: Asset,
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for MissingAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.scene_index {
+            Some(index) => write!(f, "scene {index}: ")?,
+            None => write!(f, "video: ")?,
+        }
+
+        write!(
+            f,
+            "{:?} {}: {}",
+            self.asset.kind, self.asset.file, self.error
+        )
+    }
+}
+
+fn check_asset(media_dir: &Path, asset: &Asset) -> Result<(), std::io::Error> {
+    let path = media_dir.join(&asset.file);
+    let metadata = std::fs::metadata(&path)?;
+
+    if !metadata.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "is not a file",
+        ));
+    }
+
+    std::fs::File::open(path).map(|_| ())
+}
+
+/// Walks all the scenes of the video and returns every asset that is missing or can not be read.
+pub fn validate_media<F: Format>(
+    video: &UpvotesVideo<F>,
+    media_dir: impl AsRef<Path>,
+) -> Vec<MissingAsset> {
+    let media_dir = media_dir.as_ref();
+    let scene_assets = video
+        .scenes()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, scene)| {
+            scene
+                .assets()
+                .into_iter()
+                .map(move |asset| (Some(index), asset))
+        })
+        .collect::<Vec<_>>();
+
+    // subtitles are only read while rendering, the first one showing emoji asks for the emoji font
+    let subtitles_emoji = scene_assets
+        .iter()
+        .filter(|(_, asset)| asset.kind == AssetKind::Subtitles)
+        .find_map(|(scene_index, asset)| {
+            let subtitles = std::fs::read_to_string(media_dir.join(&asset.file)).ok()?;
+
+            emoji::contains_emoji(&subtitles).then(|| {
+                (
+                    *scene_index,
+                    Asset::font(video.story.theme().fonts.emoji_file),
+                )
+            })
+        });
+
+    video
+        .assets()
+        .into_iter()
+        .map(|asset| (None, asset))
+        .chain(scene_assets)
+        .chain(subtitles_emoji)
+        .filter_map(|(scene_index, asset)| {
+            check_asset(media_dir, &asset)
+                .err()
+                .map(|error| MissingAsset {
+                    scene_index,
+                    asset,
+                    error,
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,267 @@
+use crate::shared::measure_text_width;
+use crate::theme::Theme;
+use fframes::{serde::Deserialize, svgr, Svgr};
+
+/// Distinguished authors get a badge next to their name.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+/// Free-form user flair of the subreddit.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(crate = "fframes::serde")]
+pub struct Flair {
+    pub text: String,
+    /// Hex background of the flair, the theme one when missing.
+    pub color: Option<String>,
+}
+
+/// Comment author, written in the manifest either as the bare username or as a table with the
+/// badges.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(crate = "fframes::serde", from = "AuthorManifest")]
+pub struct Author {
+    pub name: String,
+    /// Author of the thread.
+    pub op: bool,
+    pub role: Role,
+    pub flair: Option<Flair>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "fframes::serde", untagged)]
+enum AuthorManifest {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        op: bool,
+        #[serde(default)]
+        role: Role,
+        flair: Option<Flair>,
+    },
+}
+
+impl From<AuthorManifest> for Author {
+    fn from(manifest: AuthorManifest) -> Self {
+        match manifest {
+            AuthorManifest::Name(name) => Author {
+                name,
+                ..Default::default()
+            },
+            AuthorManifest::Full {
+                name,
+                op,
+                role,
+                flair,
+            } => Author {
+                name,
+                op,
+                role,
+                flair,
+            },
+        }
+    }
+}
+
+pub const HEADER_LINE_HEIGHT: f32 = 56.;
+const HEADER_GAP: f32 = 12.;
+const NAME_FONT_SIZE: usize = 40;
+const BADGE_FONT_SIZE: usize = 30;
+const FLAIR_FONT_SIZE: usize = 26;
+const FLAIR_PADDING: f32 = 12.;
+const FLAIR_HEIGHT: f32 = 40.;
+
+/// Piece of a comment header, rendered once the header layout gives it a position.
+pub struct HeaderItem<'a> {
+    width: f32,
+    render: Box<dyn Fn(f32, f32) -> Svgr + 'a>,
+}
+
+impl<'a> HeaderItem<'a> {
+    /// `render` gets the left edge and the vertical middle of the item.
+    pub fn new(width: f32, render: impl Fn(f32, f32) -> Svgr + 'a) -> Self {
+        Self {
+            width,
+            render: Box::new(render),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    fn text(width: f32, text: String, font_size: usize, weight: usize, fill: &'a str) -> Self {
+        Self::new(width, move |x, y| {
+            svgr!(
+                <text
+                    x={x}
+                    y={y + 5.}
+                    dominant-baseline="middle"
+                    font-size={font_size}
+                    font-weight={weight}
+                    fill={fill}
+                >
+                    {text.as_str()}
+                </text>
+            )
+        })
+    }
+}
+
+/// Lays the items out left to right starting at `x`, moving them to the next line once the
+/// header gets wider than `max_width`. Returns the items with the amount of lines taken.
+pub fn flow_header(items: Vec<HeaderItem>, x: f32, y: f32, max_width: f32) -> (Vec<Svgr>, usize) {
+    let mut line = 0;
+    let mut cursor = 0.;
+
+    let svgr = items
+        .into_iter()
+        .map(|item| {
+            if cursor > 0. && cursor + item.width > max_width {
+                line += 1;
+                cursor = 0.;
+            }
+
+            let rendered = (item.render)(x + cursor, y + line as f32 * HEADER_LINE_HEIGHT);
+            cursor += item.width + HEADER_GAP;
+
+            rendered
+        })
+        .collect();
+
+    (svgr, line + 1)
+}
+
+/// Black or white, whichever reads better on the given hex background.
+fn contrasting_text(background: &str) -> &'static str {
+    let hex = background.trim_start_matches('#');
+    let channel = |index: usize| {
+        let digits = match hex.len() {
+            3 => hex.get(index..index + 1).map(|digit| digit.repeat(2)),
+            _ => hex.get(index * 2..index * 2 + 2).map(str::to_owned),
+        };
+
+        digits
+            .and_then(|digits| u8::from_str_radix(&digits, 16).ok())
+            .map_or(0., |value| value as f32 / 255.)
+    };
+
+    let luminance = 0.299 * channel(0) + 0.587 * channel(1) + 0.114 * channel(2);
+    if luminance > 0.6 {
+        "#000"
+    } else {
+        "#fff"
+    }
+}
+
+/// Username followed by the OP, role and flair badges. Only the name is shown greyed out for
+/// authors of hidden comments.
+pub fn author_items<'a>(
+    frame: &fframes::Frame,
+    ctx: &fframes::FFramesContext,
+    theme: &'a Theme,
+    author: &'a Author,
+    hidden_name: Option<&'a str>,
+) -> Vec<HeaderItem<'a>> {
+    let measure =
+        |text: &str, font_size| measure_text_width(frame, ctx, text, theme.fonts.body, font_size);
+    let badge = |text: &str, fill| {
+        HeaderItem::text(
+            measure(text, BADGE_FONT_SIZE),
+            text.to_owned(),
+            BADGE_FONT_SIZE,
+            700,
+            fill,
+        )
+    };
+
+    if let Some(name) = hidden_name {
+        return vec![HeaderItem::text(
+            measure(name, NAME_FONT_SIZE),
+            name.to_owned(),
+            NAME_FONT_SIZE,
+            400,
+            theme.palette.muted_text,
+        )];
+    }
+
+    let mut items = vec![HeaderItem::text(
+        measure(&author.name, NAME_FONT_SIZE),
+        author.name.clone(),
+        NAME_FONT_SIZE,
+        400,
+        theme.palette.text,
+    )];
+
+    if author.op {
+        items.push(badge("OP", theme.palette.op));
+    }
+
+    match author.role {
+        Role::User => {}
+        Role::Moderator => items.push(badge("MOD", theme.palette.moderator)),
+        Role::Admin => items.push(badge("ADMIN", theme.palette.admin)),
+    }
+
+    if let Some(flair) = &author.flair {
+        let background = flair.color.as_deref().unwrap_or(theme.palette.flair);
+        let fill = match flair.color {
+            Some(_) => contrasting_text(background),
+            None => theme.palette.text,
+        };
+        let width = measure(&flair.text, FLAIR_FONT_SIZE) + FLAIR_PADDING * 2.;
+
+        items.push(HeaderItem::new(width, move |x, y| {
+            svgr!(
+                <rect x={x} y={y - FLAIR_HEIGHT / 2.} width={width} height={FLAIR_HEIGHT} rx="8" fill={background} />
+                <text
+                    x={x + FLAIR_PADDING}
+                    y={y + 3.}
+                    dominant-baseline="middle"
+                    font-size={FLAIR_FONT_SIZE}
+                    fill={fill}
+                >
+                    {flair.text.as_str()}
+                </text>
+            )
+        }));
+    }
+
+    items
+}
+
+/// Age of the comment, prefixed with the delimiter separating it from the author.
+pub fn posted_item<'a>(
+    frame: &fframes::Frame,
+    ctx: &fframes::FFramesContext,
+    theme: &'a Theme,
+    delimiter: &'a str,
+    posted_ago: String,
+) -> HeaderItem<'a> {
+    let delimiter = delimiter.trim();
+    let delimiter_width = match delimiter {
+        "" => 0.,
+        _ => {
+            measure_text_width(frame, ctx, delimiter, theme.fonts.body, NAME_FONT_SIZE) + HEADER_GAP
+        }
+    };
+    let width = delimiter_width
+        + measure_text_width(frame, ctx, &posted_ago, theme.fonts.body, BADGE_FONT_SIZE);
+
+    HeaderItem::new(width, move |x, y| {
+        svgr!(
+            <text x={x} y={y + 5.} dominant-baseline="middle" font-size={NAME_FONT_SIZE} fill={theme.palette.text}>
+                {delimiter}
+                <tspan x={x + delimiter_width} font-size={BADGE_FONT_SIZE} fill={theme.palette.muted_text}>
+                    {posted_ago.as_str()}
+                </tspan>
+            </text>
+        )
+    })
+}
@@ -1,6 +1,15 @@
+use crate::assets::{Asset, SceneAssets};
+use crate::emoji;
+use crate::format::Canvas;
+use crate::locale::{Locale, TimeStyle};
+use crate::mixing::Mixer;
+use crate::sfx::{cue_assets, cue_tracks, Cue, CueAt, CueTime, SceneCues, SoundPack};
+use crate::shared::{seconds_to_frames, SPRING};
+use crate::theme::Theme;
+use crate::timing::{SceneTiming, Timing};
 use fframes::{
-    svgr, AudioMap, AudioTimestamp, BreakLinesOpts, Easing, KeyFrame, KeyFramesAnimation, Scene,
-    Svgr, TextAlign,
+    svgr, AudioMap, AudioTimestamp, BreakLinesOpts, KeyFrame, KeyFramesAnimation, Scene, Svgr,
+    TextAlign,
 };
 
 #[derive(Debug)]
@@ -9,40 +18,104 @@ pub enum Variant<'a> {
     Title { audio_file: &'a str },
 }
 
+/// The outro bubble springs out this many seconds into the scene.
+const EXIT_POP_SECOND: f32 = 0.1;
+/// The intro bubble shrinks away this many seconds before the end of the scene.
+const TITLE_SHRINK_LEAD: f32 = 0.9;
+
+const AVATAR_IMAGE: &str = "avatar.png";
+const UPVOTE_ARROW_IMAGE: &str = "upvote_arrow.png";
+
 #[derive(Debug)]
 pub struct BubbleTransitionScene<'a> {
     pub fill: &'a str,
     pub variant: Variant<'a>,
     pub title: &'a str,
-    pub upvotes_count: &'a str,
+    pub upvotes_count: u64,
     pub author: &'a str,
-    pub when: &'a str,
+    /// Age of the thread at the reference date of the video.
+    pub posted_ago: chrono::Duration,
+    pub canvas: Canvas,
+    pub theme: Theme,
+    pub locale: Locale,
+    pub mixer: &'a Mixer,
+    pub sounds: SoundPack<'a>,
 }
 
-impl Scene for BubbleTransitionScene<'_> {
-    fn audio_map(&self, _: &fframes::SceneInfo) -> fframes::audio_map::AudioMap {
-        match self.variant {
-            Variant::Exit => AudioMap::none(),
-            Variant::Title { audio_file, .. } => {
-                AudioMap::from([(audio_file, (AudioTimestamp::Second(0), AudioTimestamp::Eof))])
+impl SceneAssets for BubbleTransitionScene<'_> {
+    fn assets(&self) -> Vec<Asset> {
+        // the outro slides the thread header with its upvotes out too
+        let mut assets = vec![Asset::image(AVATAR_IMAGE), Asset::image(UPVOTE_ARROW_IMAGE)];
+        assets.extend(cue_assets(&self.cues(), &self.sounds, self.mixer));
+
+        if let Variant::Title { audio_file } = self.variant {
+            assets.push(Asset::track(
+                audio_file,
+                self.mixer.narration_mix(audio_file),
+            ));
+
+            if emoji::contains_emoji(self.title) {
+                assets.push(Asset::font(self.theme.fonts.emoji_file));
             }
         }
+
+        assets
     }
+}
 
-    fn duration(&self) -> fframes::video::Duration {
+impl SceneCues for BubbleTransitionScene<'_> {
+    fn cues(&self) -> Vec<CueAt<'_>> {
+        let time = match self.variant {
+            Variant::Exit => CueTime::Frame(seconds_to_frames(EXIT_POP_SECOND)),
+            Variant::Title { .. } => CueTime::BeforeEnd(seconds_to_frames(TITLE_SHRINK_LEAD)),
+        };
+
+        vec![CueAt::new(Cue::Pop, time)]
+    }
+}
+
+impl SceneTiming for BubbleTransitionScene<'_> {
+    fn timing(&self) -> Timing<'_> {
         match self.variant {
-            Variant::Exit => fframes::Duration::Seconds(2.7),
-            Variant::Title { audio_file, .. } => {
-                fframes::Duration::FromAudio(audio_file) + fframes::Duration::Seconds(0.4)
-            }
+            Variant::Title { audio_file } => Timing {
+                narration: Some(self.mixer.narration(audio_file)),
+                tail: self.mixer.silence.bubble_pad,
+                overlap: 0.94,
+            },
+            Variant::Exit => Timing {
+                narration: None,
+                tail: 2.7,
+                overlap: 0.9,
+            },
         }
     }
+}
+
+impl Scene for BubbleTransitionScene<'_> {
+    fn audio_map(&self, scene_info: &fframes::SceneInfo) -> fframes::audio_map::AudioMap {
+        let narration = match self.variant {
+            Variant::Exit => None,
+            Variant::Title { audio_file, .. } => Some((
+                self.mixer.narration(audio_file),
+                (AudioTimestamp::Second(0), AudioTimestamp::Eof),
+            )),
+        };
+        let cues = self.cues();
+
+        AudioMap::from_iter(narration.into_iter().chain(cue_tracks(
+            &cues,
+            &self.sounds,
+            self.mixer,
+            scene_info,
+        )))
+    }
+
+    fn duration(&self) -> fframes::video::Duration {
+        self.timing().duration()
+    }
 
     fn overlap(&self) -> fframes::Overlap {
-        match self.variant {
-            Variant::Title { .. } => fframes::Overlap::Next(0.94),
-            Variant::Exit => fframes::Overlap::Next(0.9),
-        }
+        self.timing().overlap()
     }
 
     fn render_frame(
@@ -50,14 +123,21 @@ impl Scene for BubbleTransitionScene<'_> {
         mut frame: fframes::frame::Frame,
         ctx: &fframes::FFramesContext,
     ) -> fframes::Svgr {
-        const SPRING: Easing = Easing::Spring2(1.0, 80., 16.);
         let scene_info = ctx.get_scene_info(self).unwrap();
         let scale = match self.variant {
-            Variant::Exit => frame.animate(&fframes::timeline!(
-                on 0.1, val 0.1 => 12., &SPRING
-            )),
+            Variant::Exit => {
+                let timeline = KeyFramesAnimation::new(vec![KeyFrame {
+                    easing: &SPRING,
+                    start: EXIT_POP_SECOND,
+                    from: 0.1,
+                    to: 12.,
+                }]);
+
+                frame.animate(&timeline)
+            }
             Variant::Title { .. } => {
-                let start = frame.frame_to_second(scene_info.duration_in_frames) - 0.9;
+                let start =
+                    frame.frame_to_second(scene_info.duration_in_frames) - TITLE_SHRINK_LEAD;
                 let timeline = KeyFramesAnimation::new(vec![KeyFrame {
                     easing: &SPRING,
                     start,
@@ -75,7 +155,7 @@ impl Scene for BubbleTransitionScene<'_> {
                     easing: &SPRING,
                     start,
                     from: 0.,
-                    to: 1080.,
+                    to: self.canvas.width as f32,
                 }]);
 
                 frame.animate(&timeline)
@@ -84,47 +164,54 @@ impl Scene for BubbleTransitionScene<'_> {
         };
 
         let translate = match self.variant {
-            Variant::Exit => "",
-            Variant::Title { .. } => "translate(700, 1299)",
+            Variant::Exit => String::new(),
+            Variant::Title { .. } => format!(
+                "translate({}, {})",
+                self.canvas.column_x() + 700,
+                self.canvas.scale_y(1299)
+            ),
         };
 
-        const X: usize = 110;
-        const Y: usize = 400;
+        let left = self.canvas.column_x() + 110;
+        let top = self.canvas.scale_y(400);
 
-        let x = X.to_string();
-        let y = Y.to_string();
+        let x = left.to_string();
+        let y = top.to_string();
 
         let opts = BreakLinesOpts {
             x: x.as_str(),
             y: y.as_str(),
-            font_family: "KyivType Serif",
+            font_family: self.theme.fonts.heading,
             font_weight: 900,
             font_size: 80,
             line_height: 1.2,
             width: 900,
             align: TextAlign::Left,
-            fill: "#000",
+            fill: self.theme.palette.text,
             ..Default::default()
         };
 
-        let subtitle = format!("Posted by {} {}", self.author, self.when);
-        let title_wrapped_structure = frame
-            .text_break_lines_strcuture(ctx, self.title, &opts)
-            .unwrap_or_default();
+        let subtitle = format!(
+            "Posted by {} {}",
+            self.author,
+            self.locale.relative_time(self.posted_ago, TimeStyle::Long)
+        );
+        let title_wrapped_structure =
+            emoji::break_lines(&frame, ctx, self.title, &opts).unwrap_or_default();
 
         let title_height = 60 + title_wrapped_structure.occupied_height();
 
         let upvotes = svgr!(
             <image
-                href={ctx.get_image_link("upvote_arrow.png")}
-                x={X}
-                y={Y + title_height }
+                href={ctx.get_image_link(UPVOTE_ARROW_IMAGE)}
+                x={left}
+                y={top + title_height }
                 width="60"
                 height="60"
             />
 
-            <text x={X + 70} y={Y + title_height + 10} fill="black" font-size="50" font-family="Nunito" font-weight="bold" dominant-baseline="hanging">
-                {self.upvotes_count}" upvotes"
+            <text x={left + 70} y={top + title_height + 10} fill={self.theme.palette.text} font-size="50" font-family={self.theme.fonts.meta} font-weight="bold" dominant-baseline="hanging">
+                {self.locale.compact_count(self.upvotes_count)}" upvotes"
             </text>
         );
 
@@ -142,20 +229,20 @@ impl Scene for BubbleTransitionScene<'_> {
 
             <g id="test" clip-path="url(#containerClip)">
                 <image
-                    y={940}
-                    x={0}
-                    width={1080}
-                    href={ctx.get_image_link("avatar.png")}
+                    y={self.canvas.scale_y(940)}
+                    x={self.canvas.column_x()}
+                    width={self.canvas.column_width()}
+                    href={ctx.get_image_link(AVATAR_IMAGE)}
                 />
 
                 <g transform={format!("translate({text_translate})")}>
                     <text
-                       x={X}
-                       y={Y - 110}
-                       font-family="Nunito"
+                       x={left}
+                       y={top - 110}
+                       font-family={self.theme.fonts.meta}
                        font-weight="bold"
                        font-size="50"
-                       fill="#3f3f46"
+                       fill={self.theme.palette.secondary_text}
                     >
                         {
                             match self.variant {
@@ -170,20 +257,21 @@ impl Scene for BubbleTransitionScene<'_> {
                             Variant::Title { .. } => {
 
                                 svgr!(
-                                    {title_wrapped_structure.as_svgr(&opts)}
+                                    {emoji::render_lines(&title_wrapped_structure, &opts, self.title, self.theme.fonts.emoji)}
                                     {upvotes.clone()}
                                 )
                             },
                             Variant::Exit => svgr!(
                                 <text
-                                   x={X}
-                                   y={Y + 50}
+                                   x={left}
+                                   y={top + 50}
                                    font-family={opts.font_family}
                                    font-weight="black"
                                    font-size="140"
+                                   fill={opts.fill}
                                 >
                                     "Subscribe"
-                                    <tspan x={X} y={Y + 50} dy="1em">
+                                    <tspan x={left} y={top + 50} dy="1em">
                                      "for more"
                                     </tspan>
                                 </text>
@@ -195,15 +283,15 @@ impl Scene for BubbleTransitionScene<'_> {
                 {
                     match self.variant {
                         Variant::Exit => svgr!(
-                            <g transform={format!("translate({})", text_translate - 1080.)}>
+                            <g transform={format!("translate({})", text_translate - self.canvas.width as f32)}>
                                 <text
                                    id="subtitle"
-                                   x={X}
-                                   y={Y - 110}
-                                   font-family="Nunito"
+                                   x={left}
+                                   y={top - 110}
+                                   font-family={self.theme.fonts.meta}
                                    font-weight="bold"
                                    font-size="50"
-                                   fill="#3f3f46"
+                                   fill={self.theme.palette.secondary_text}
                                 >
                                     {subtitle}
                                 </text>
@@ -0,0 +1,255 @@
+use fframes::{svgr, BreakLinesOpts, FFramesContext, Frame, Svgr, WrappedTextStructure};
+use std::borrow::Cow;
+
+/// Stands in for every emoji while the lines are broken. The text fonts have no emoji glyphs
+/// and would measure them as nothing, a wide letter is about as wide as a color emoji.
+const LAYOUT_PLACEHOLDER: char = 'W';
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+/// Pictographs drawn with the emoji font.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xa9 | 0xae
+            | 0x203c
+            | 0x2049
+            // the technical and arrow blocks are mostly symbols the emoji font has no glyph for
+            | 0x231a..=0x231b
+            | 0x2328
+            | 0x23cf
+            | 0x23e9..=0x23f3
+            | 0x23f8..=0x23fa
+            | 0x2600..=0x27bf
+            | 0x2b05..=0x2b07
+            | 0x2b1b..=0x2b1c
+            | 0x2b50
+            | 0x2b55
+            | 0x1f000..=0x1faff
+    )
+}
+
+/// Digits, `#` and `*` are emoji only when the combining keycap follows them, optionally after a
+/// variation selector.
+fn starts_keycap(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    matches!(chars.next(), Some('0'..='9' | '#' | '*'))
+        && matches!(
+            (chars.next(), chars.next()),
+            (Some('\u{20e3}'), _) | (Some('\u{fe0f}'), Some('\u{20e3}'))
+        )
+}
+
+/// Joiners, variation selectors, skin tones and tags continuing the emoji before them.
+fn is_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x200d | 0xfe0e | 0xfe0f | 0x20e3 | 0x1f3fb..=0x1f3ff | 0xe0020..=0xe007f
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1f1e6..=0x1f1ff)
+}
+
+pub fn contains_emoji(text: &str) -> bool {
+    text.char_indices()
+        .any(|(index, c)| is_emoji(c) || starts_keycap(&text[index..]))
+}
+
+/// Splits the text into runs of emoji and of regular text, emoji runs are flagged `true`.
+fn runs(text: &str) -> Vec<(&str, bool)> {
+    let mut runs: Vec<(&str, bool)> = Vec::new();
+    let mut start = 0;
+    let mut in_emoji = false;
+
+    for (index, c) in text.char_indices() {
+        let emoji = is_emoji(c) || starts_keycap(&text[index..]) || (in_emoji && is_modifier(c));
+        if emoji != in_emoji && index > start {
+            runs.push((&text[start..index], in_emoji));
+            start = index;
+        }
+
+        in_emoji = emoji;
+    }
+
+    if start < text.len() {
+        runs.push((&text[start..], in_emoji));
+    }
+
+    runs
+}
+
+/// Amount of emoji glyphs of an emoji run. Sequences joined with a zero width joiner and pairs of
+/// regional indicators forming a flag are drawn as a single glyph.
+fn glyph_count(run: &str) -> usize {
+    let mut count = 0;
+    let mut joined = false;
+    let mut open_flag = false;
+
+    for c in run.chars() {
+        if is_modifier(c) {
+            joined = c == ZERO_WIDTH_JOINER;
+            continue;
+        }
+
+        if is_regional_indicator(c) {
+            if !open_flag {
+                count += 1;
+            }
+
+            open_flag = !open_flag;
+        } else if !joined {
+            count += 1;
+            open_flag = false;
+        }
+
+        joined = false;
+    }
+
+    count
+}
+
+/// Text measured in place of the given one, every emoji replaced with the placeholder. Keeps the
+/// whitespace as it is, so the words of the laid out lines are the words of the original text.
+fn layout_text(text: &str) -> Cow<'_, str> {
+    if !contains_emoji(text) {
+        return Cow::Borrowed(text);
+    }
+
+    runs(text)
+        .into_iter()
+        .map(|(run, emoji)| match emoji {
+            true => Cow::Owned(LAYOUT_PLACEHOLDER.to_string().repeat(glyph_count(run))),
+            false => Cow::Borrowed(run),
+        })
+        .collect::<String>()
+        .into()
+}
+
+/// Breaks the text into lines measuring emoji as wide as the glyphs of the emoji font. The words
+/// of the returned lines are the placeholders, render the words of the text itself by position.
+///
+/// Drawing by position relies on the layout keeping every whitespace separated word whole, the
+/// placeholders never add or remove whitespace.
+pub fn break_lines(
+    frame: &Frame,
+    ctx: &FFramesContext,
+    text: &str,
+    opts: &BreakLinesOpts,
+) -> Option<WrappedTextStructure> {
+    let layout_text = layout_text(text);
+    let structure = frame.text_break_lines_strcuture(ctx, &layout_text, opts)?;
+
+    debug_assert_eq!(
+        structure
+            .lines
+            .iter()
+            .map(|line| line.words.len())
+            .sum::<usize>(),
+        layout_text.split_whitespace().count(),
+        "the laid out words no longer match the words of the text"
+    );
+
+    Some(structure)
+}
+
+/// A single word, its emoji in the emoji font.
+pub fn word(word: &str, emoji_font: &str) -> Vec<Svgr> {
+    runs(word)
+        .into_iter()
+        .map(|(run, emoji)| match emoji {
+            true => svgr!(<tspan font-family={emoji_font}>{run}</tspan>),
+            false => svgr!({ run }),
+        })
+        .collect()
+}
+
+/// Same as `WrappedTextStructure::as_svgr` for lines laid out by `break_lines`, drawing the
+/// emoji of the text with the emoji font.
+pub fn render_lines(
+    structure: &WrappedTextStructure,
+    opts: &BreakLinesOpts,
+    text: &str,
+    emoji_font: &str,
+) -> Svgr {
+    if !contains_emoji(text) {
+        return structure.as_svgr(opts);
+    }
+
+    let mut words = text.split_whitespace();
+    let lines = structure
+        .lines
+        .iter()
+        .map(|line| {
+            let line_words = line
+                .words
+                .iter()
+                .filter_map(|_| words.next())
+                .map(|text_word| svgr!({word(text_word, emoji_font)}{" "}))
+                .collect::<Vec<_>>();
+
+            svgr!(
+                <tspan x={opts.x} y={opts.y} dx={line.dx} dy={line.dy.to_string()}>
+                    {line_words}
+                </tspan>
+            )
+        })
+        .collect::<Vec<_>>();
+
+    svgr!(
+        <text
+            x={opts.x}
+            y={opts.y}
+            fill={opts.fill}
+            font-size={opts.font_size}
+            font-family={opts.font_family}
+            font-weight={opts.font_weight}
+        >
+            {lines}
+        </text>
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_emoji_but_not_other_symbols() {
+        assert!(contains_emoji("on my way 🚀"));
+        assert!(contains_emoji("⌛ and ⭐"));
+        assert!(contains_emoji("1\u{fe0f}\u{20e3}"));
+        assert!(!contains_emoji("⌘ + c, ⬈ and ⯑"));
+        assert!(!contains_emoji("10 # 3 * 2"));
+    }
+
+    #[test]
+    fn splits_emoji_runs_from_the_text() {
+        assert_eq!(
+            runs("hi👋🏽there"),
+            vec![("hi", false), ("👋🏽", true), ("there", false)]
+        );
+        assert_eq!(
+            runs("#\u{fe0f}\u{20e3}1"),
+            vec![("#\u{fe0f}\u{20e3}", true), ("1", false)]
+        );
+        assert_eq!(runs("⌘⭐"), vec![("⌘", false), ("⭐", true)]);
+    }
+
+    #[test]
+    fn counts_joined_sequences_and_flags_as_one_glyph() {
+        // family joined with zero width joiners
+        assert_eq!(glyph_count("👨\u{200d}👩\u{200d}👧"), 1);
+        // two flags
+        assert_eq!(glyph_count("🇺🇦🇫🇷"), 2);
+        assert_eq!(glyph_count("👍🏻👍"), 2);
+    }
+
+    #[test]
+    fn lays_emoji_out_as_placeholders_keeping_the_words() {
+        assert_eq!(layout_text("no emoji"), "no emoji");
+        assert_eq!(layout_text("so 😂😂 funny 🇺🇦"), "so WW funny W");
+    }
+}
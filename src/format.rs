@@ -0,0 +1,82 @@
+/// Canvas size of the video, fframes reads it from the `Video` constants so every format is its
+/// own type.
+pub trait Format {
+    const WIDTH: usize;
+    const HEIGHT: usize;
+}
+
+/// 9:16, shorts, reels and tiktok.
+#[derive(Debug)]
+pub struct Portrait;
+
+/// 16:9, regular youtube videos.
+#[derive(Debug)]
+pub struct Landscape;
+
+/// 1:1 feed posts.
+#[derive(Debug)]
+pub struct Square;
+
+/// 4:5 feed posts.
+#[derive(Debug)]
+pub struct Feed;
+
+impl Format for Portrait {
+    const WIDTH: usize = 1080;
+    const HEIGHT: usize = 1920;
+}
+
+impl Format for Landscape {
+    const WIDTH: usize = 1920;
+    const HEIGHT: usize = 1080;
+}
+
+impl Format for Square {
+    const WIDTH: usize = 1080;
+    const HEIGHT: usize = 1080;
+}
+
+impl Format for Feed {
+    const WIDTH: usize = 1080;
+    const HEIGHT: usize = 1350;
+}
+
+/// Scenes are designed for the 1080px wide portrait canvas. Other formats keep the same column
+/// of content centered horizontally and derive the vertical positions from the canvas height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+}
+
+const DESIGN_WIDTH: usize = 1080;
+const DESIGN_HEIGHT: usize = 1920;
+
+impl Canvas {
+    pub fn of<F: Format>() -> Self {
+        Self {
+            width: F::WIDTH,
+            height: F::HEIGHT,
+        }
+    }
+
+    pub fn column_width(&self) -> usize {
+        self.width.min(DESIGN_WIDTH)
+    }
+
+    /// Left edge of the content column.
+    pub fn column_x(&self) -> usize {
+        (self.width - self.column_width()) / 2
+    }
+
+    /// Moves a vertical position of the portrait design onto this canvas.
+    pub fn scale_y(&self, y: usize) -> usize {
+        y * self.height / DESIGN_HEIGHT
+    }
+}
+
+impl Default for Canvas {
+    fn default() -> Self {
+        Self::of::<Portrait>()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::theme::Theme;
+use fframes::{serde::Deserialize, AnimateRuntimeInput, AnimationRuntime, Color};
+
+/// Look of the karaoke highlight following the narration, set for the whole video with the top
+/// level `highlight_style` key of the manifest or for a single comment.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum HighlightStyle {
+    /// Heavier current word in the highlight color.
+    #[default]
+    Bold,
+    /// Only the color of the current word changes.
+    Color,
+    /// Rounded pill in the highlight color behind the current word.
+    Pill,
+    /// Underline sweeping under the current word.
+    Underline,
+    /// Current word grows and settles back.
+    Pop,
+    /// Upcoming words fade out the further they are from the current one.
+    Fade,
+}
+
+/// Where a word of the paragraph is relative to the narration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordState {
+    Spoken,
+    /// Said right before the current word, turning back into a spoken one.
+    Previous,
+    Current,
+    Upcoming {
+        distance: usize,
+    },
+}
+
+/// How a single word is drawn at the current frame.
+#[derive(Debug, Clone, Copy)]
+pub struct WordLook {
+    pub weight: f32,
+    pub fill: Color,
+    pub opacity: f32,
+    /// Scale of the popping word, drawn over the line so the line itself keeps its layout.
+    pub font_scale: f32,
+}
+
+const TRANSITION_DURATION: f32 = 0.15;
+const POP_DURATION: f32 = 0.3;
+const POP_SCALE: f32 = 0.2;
+const FADE_STEP: f32 = 0.12;
+const FADE_MIN_OPACITY: f32 = 0.2;
+
+/// Animates the change of the current word, starting at the second the word started. Words
+/// without a known start switch at once.
+#[derive(Clone, Copy)]
+pub struct Transition<'a> {
+    frame: &'a fframes::Frame,
+    start: Option<f32>,
+}
+
+impl<'a> Transition<'a> {
+    pub fn new(frame: &'a fframes::Frame, start: Option<f32>) -> Self {
+        Self { frame, start }
+    }
+
+    fn progress(self, duration: f32) -> f32 {
+        match self.start {
+            Some(start) => self.frame.animate_runtime(AnimateRuntimeInput {
+                on_second: start,
+                from: 0.,
+                to: 1.,
+                animation_runtime: &AnimationRuntime::Linear(duration),
+            }),
+            None => 1.,
+        }
+    }
+
+    fn color(self, from: &str, to: &str) -> Color {
+        match self.start {
+            Some(start) => self.frame.animate_runtime(AnimateRuntimeInput {
+                on_second: start,
+                from: Color::hex(from),
+                to: Color::hex(to),
+                animation_runtime: &AnimationRuntime::Linear(TRANSITION_DURATION),
+            }),
+            None => Color::hex(to),
+        }
+    }
+
+    /// Progress of the current word's entrance, `0` when it just started.
+    pub fn entrance(self) -> f32 {
+        self.progress(TRANSITION_DURATION)
+    }
+}
+
+impl HighlightStyle {
+    /// `spoken` is the fill of the already narrated word, quotes use a muted one.
+    pub fn word_look(
+        self,
+        theme: &Theme,
+        state: WordState,
+        spoken: &str,
+        transition: Transition,
+    ) -> WordLook {
+        let highlight = theme.highlight;
+        let current = match self {
+            HighlightStyle::Pill => theme.palette.on_accent,
+            _ => highlight.current,
+        };
+        let current_weight = match self {
+            HighlightStyle::Bold => 900.,
+            HighlightStyle::Pop => 700.,
+            _ => 400.,
+        };
+
+        let look = WordLook {
+            weight: 400.,
+            fill: Color::hex(spoken),
+            opacity: 1.,
+            font_scale: 1.,
+        };
+
+        match state {
+            WordState::Spoken => look,
+            WordState::Previous => {
+                let progress = transition.entrance();
+
+                WordLook {
+                    weight: current_weight + (400. - current_weight) * progress,
+                    fill: transition.color(current, spoken),
+                    ..look
+                }
+            }
+            WordState::Current => {
+                let progress = transition.entrance();
+                let font_scale = match self {
+                    HighlightStyle::Pop => {
+                        let pop = transition.progress(POP_DURATION);
+                        1. + POP_SCALE * (pop * std::f32::consts::PI).sin()
+                    }
+                    _ => 1.,
+                };
+
+                WordLook {
+                    weight: 400. + (current_weight - 400.) * progress,
+                    fill: transition.color(highlight.upcoming, current),
+                    font_scale,
+                    ..look
+                }
+            }
+            WordState::Upcoming { distance } => WordLook {
+                fill: Color::hex(highlight.upcoming),
+                opacity: match self {
+                    HighlightStyle::Fade => {
+                        (1. - distance as f32 * FADE_STEP).max(FADE_MIN_OPACITY)
+                    }
+                    _ => 1.,
+                },
+                ..look
+            },
+        }
+    }
+}
@@ -1,5 +1,21 @@
+pub mod alignment;
+pub mod analysis;
+pub mod assets;
+pub mod author;
 mod bubble;
+mod emoji;
+pub mod format;
+pub mod highlight;
+pub mod locale;
+pub mod loudness;
+pub mod markdown;
+pub mod mixing;
 pub mod post;
+pub mod sfx;
 mod shared;
-mod title;
+pub mod silence;
+pub mod story;
+pub mod theme;
+pub mod timing;
+pub mod title;
 pub mod upvotes_video;
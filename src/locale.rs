@@ -0,0 +1,250 @@
+use chrono::Duration;
+use fframes::serde::Deserialize;
+
+/// Language of the generated labels, picked with the top level `locale` key of the manifest.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Es,
+    Fr,
+    Ru,
+}
+
+const SCALES: [u64; 3] = [1_000_000_000, 1_000_000, 1_000];
+
+impl Locale {
+    fn decimal_separator(self) -> char {
+        match self {
+            Locale::En => '.',
+            Locale::De | Locale::Es | Locale::Fr | Locale::Ru => ',',
+        }
+    }
+
+    /// Suffixes of the billions, millions and thousands, in the order of `SCALES`.
+    fn compact_suffixes(self) -> [&'static str; 3] {
+        match self {
+            Locale::En => ["B", "M", "k"],
+            Locale::De => [" Mrd.", " Mio.", " Tsd."],
+            Locale::Es => [" mil M", " M", " mil"],
+            Locale::Fr => [" Md", " M", " k"],
+            Locale::Ru => [" млрд", " млн", " тыс."],
+        }
+    }
+
+    /// Reddit style short counter: `999`, `1.2k`, `24.8k`, `158k`, `1.1M`. Shows a single
+    /// truncated decimal below a hundred of the unit so the label never rounds up to the next one.
+    pub fn compact_count(self, count: u64) -> String {
+        let Some((scale, suffix)) = SCALES
+            .into_iter()
+            .zip(self.compact_suffixes())
+            .find(|(scale, _)| count >= *scale)
+        else {
+            return count.to_string();
+        };
+
+        let tenths = count / (scale / 10);
+        let (whole, fraction) = (tenths / 10, tenths % 10);
+
+        if fraction == 0 || whole >= 100 {
+            format!("{whole}{suffix}")
+        } else {
+            format!("{whole}{}{fraction}{suffix}", self.decimal_separator())
+        }
+    }
+}
+
+/// How the age of a post is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeStyle {
+    /// `5h`, `2d`, used by the comment headers.
+    Short,
+    /// `2 days ago`, used by the thread intros.
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeUnit {
+    Minute,
+    Hour,
+    Day,
+    Month,
+    Year,
+}
+
+impl TimeUnit {
+    /// The largest unit fitting the age at least once, `None` for less than a minute.
+    fn of(age: Duration) -> Option<(u64, TimeUnit)> {
+        let minutes = age.num_minutes().max(0) as u64;
+        let days = minutes / (60 * 24);
+
+        [
+            (days / 365, TimeUnit::Year),
+            (days / 30, TimeUnit::Month),
+            (days, TimeUnit::Day),
+            (minutes / 60, TimeUnit::Hour),
+            (minutes, TimeUnit::Minute),
+        ]
+        .into_iter()
+        .find(|(amount, _)| *amount > 0)
+    }
+}
+
+impl Locale {
+    /// Index of the `[one, few, many]` form used with the amount.
+    fn plural_form(self, amount: u64) -> usize {
+        match self {
+            Locale::En | Locale::De | Locale::Es if amount == 1 => 0,
+            Locale::Fr if amount <= 1 => 0,
+            Locale::Ru if amount % 10 == 1 && amount % 100 != 11 => 0,
+            Locale::Ru
+                if (2..=4).contains(&(amount % 10)) && !(12..=14).contains(&(amount % 100)) =>
+            {
+                1
+            }
+            _ => 2,
+        }
+    }
+
+    fn unit_names(self, unit: TimeUnit) -> (&'static str, [&'static str; 3]) {
+        use TimeUnit::*;
+
+        match (self, unit) {
+            (Locale::En, Minute) => ("m", ["minute", "minutes", "minutes"]),
+            (Locale::En, Hour) => ("h", ["hour", "hours", "hours"]),
+            (Locale::En, Day) => ("d", ["day", "days", "days"]),
+            (Locale::En, Month) => ("mo", ["month", "months", "months"]),
+            (Locale::En, Year) => ("y", ["year", "years", "years"]),
+            (Locale::De, Minute) => (" Min.", ["Minute", "Minuten", "Minuten"]),
+            (Locale::De, Hour) => (" Std.", ["Stunde", "Stunden", "Stunden"]),
+            (Locale::De, Day) => (" T.", ["Tag", "Tagen", "Tagen"]),
+            (Locale::De, Month) => (" Mon.", ["Monat", "Monaten", "Monaten"]),
+            (Locale::De, Year) => (" J.", ["Jahr", "Jahren", "Jahren"]),
+            (Locale::Es, Minute) => (" min", ["minuto", "minutos", "minutos"]),
+            (Locale::Es, Hour) => (" h", ["hora", "horas", "horas"]),
+            (Locale::Es, Day) => (" d", ["día", "días", "días"]),
+            (Locale::Es, Month) => (" m", ["mes", "meses", "meses"]),
+            (Locale::Es, Year) => (" a", ["año", "años", "años"]),
+            (Locale::Fr, Minute) => (" min", ["minute", "minutes", "minutes"]),
+            (Locale::Fr, Hour) => (" h", ["heure", "heures", "heures"]),
+            (Locale::Fr, Day) => (" j", ["jour", "jours", "jours"]),
+            (Locale::Fr, Month) => (" mois", ["mois", "mois", "mois"]),
+            (Locale::Fr, Year) => (" a", ["an", "ans", "ans"]),
+            (Locale::Ru, Minute) => (" мин", ["минуту", "минуты", "минут"]),
+            (Locale::Ru, Hour) => (" ч", ["час", "часа", "часов"]),
+            (Locale::Ru, Day) => (" д", ["день", "дня", "дней"]),
+            (Locale::Ru, Month) => (" мес", ["месяц", "месяца", "месяцев"]),
+            (Locale::Ru, Year) => (" г", ["год", "года", "лет"]),
+        }
+    }
+
+    /// Age of a post relative to the reference date of the video, `2d` or `2 days ago`.
+    pub fn relative_time(self, age: Duration, style: TimeStyle) -> String {
+        let Some((amount, unit)) = TimeUnit::of(age) else {
+            return match (self, style) {
+                (Locale::En, TimeStyle::Short) => "now",
+                (Locale::En, TimeStyle::Long) => "just now",
+                (Locale::De, TimeStyle::Short) => "jetzt",
+                (Locale::De, TimeStyle::Long) => "gerade eben",
+                (Locale::Es, TimeStyle::Short) => "ahora",
+                (Locale::Es, TimeStyle::Long) => "justo ahora",
+                (Locale::Fr, TimeStyle::Short) => "maintenant",
+                (Locale::Fr, TimeStyle::Long) => "à l'instant",
+                (Locale::Ru, TimeStyle::Short) => "сейчас",
+                (Locale::Ru, TimeStyle::Long) => "только что",
+            }
+            .to_owned();
+        };
+
+        let (short, long) = self.unit_names(unit);
+        match style {
+            TimeStyle::Short => format!("{amount}{short}"),
+            TimeStyle::Long => {
+                let amount = format!("{amount} {}", long[self.plural_form(amount)]);
+                match self {
+                    Locale::En => format!("{amount} ago"),
+                    Locale::De => format!("vor {amount}"),
+                    Locale::Es => format!("hace {amount}"),
+                    Locale::Fr => format!("il y a {amount}"),
+                    Locale::Ru => format!("{amount} назад"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compacts_counts_at_the_unit_boundaries() {
+        let counts = [999, 1000, 1100, 999_999, 1_100_000];
+
+        assert_eq!(
+            counts.map(|count| Locale::En.compact_count(count)),
+            ["999", "1k", "1.1k", "999k", "1.1M"]
+        );
+        assert_eq!(
+            counts.map(|count| Locale::De.compact_count(count)),
+            ["999", "1 Tsd.", "1,1 Tsd.", "999 Tsd.", "1,1 Mio."]
+        );
+    }
+
+    #[test]
+    fn truncates_instead_of_rounding_up() {
+        assert_eq!(Locale::En.compact_count(1_999), "1.9k");
+        assert_eq!(Locale::En.compact_count(24_850), "24.8k");
+        assert_eq!(Locale::En.compact_count(158_400), "158k");
+    }
+
+    fn ago(locale: Locale, hours: i64) -> String {
+        locale.relative_time(Duration::hours(hours), TimeStyle::Long)
+    }
+
+    #[test]
+    fn picks_the_russian_plural_forms() {
+        assert_eq!(ago(Locale::Ru, 1), "1 час назад");
+        assert_eq!(ago(Locale::Ru, 3), "3 часа назад");
+        assert_eq!(ago(Locale::Ru, 5), "5 часов назад");
+        assert_eq!(ago(Locale::Ru, 11), "11 часов назад");
+        assert_eq!(ago(Locale::Ru, 21), "21 час назад");
+        assert_eq!(ago(Locale::Ru, 22), "22 часа назад");
+        assert_eq!(
+            Locale::Ru.relative_time(Duration::days(365 * 12), TimeStyle::Long),
+            "12 лет назад"
+        );
+    }
+
+    #[test]
+    fn picks_the_german_plural_forms() {
+        assert_eq!(ago(Locale::De, 1), "vor 1 Stunde");
+        assert_eq!(ago(Locale::De, 2), "vor 2 Stunden");
+        assert_eq!(
+            Locale::De.relative_time(Duration::days(1), TimeStyle::Long),
+            "vor 1 Tag"
+        );
+        assert_eq!(
+            Locale::De.relative_time(Duration::days(3), TimeStyle::Long),
+            "vor 3 Tagen"
+        );
+    }
+
+    #[test]
+    fn writes_short_ages_and_recent_posts() {
+        assert_eq!(
+            Locale::De.relative_time(Duration::hours(5), TimeStyle::Short),
+            "5 Std."
+        );
+        assert_eq!(
+            Locale::Ru.relative_time(Duration::seconds(30), TimeStyle::Long),
+            "только что"
+        );
+        assert_eq!(
+            Locale::En.relative_time(Duration::days(45), TimeStyle::Short),
+            "1mo"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::mixing::{run, trim_filters, MixingError};
+use fframes::serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+/// Loudness normalization of the narration and the limiter of the rendered video, set with the
+/// top level `[loudness]` table of the manifest.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "fframes::serde", default)]
+pub struct Loudness {
+    /// Turns both the normalization and the limiter off.
+    pub enabled: bool,
+    /// Integrated loudness every narration clip is brought to, in LUFS.
+    pub target: f32,
+    /// Ceiling of the true-peak limiter run on the final mix, in dBTP.
+    pub true_peak: f32,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            // the level shorts and reels are normalized to
+            target: -16.,
+            true_peak: -1.,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "fframes::serde")]
+struct LoudnormReport {
+    input_i: String,
+}
+
+/// Integrated loudness of the seconds of the file given by `window` in LUFS, measured with the
+/// first pass of ffmpeg's `loudnorm`.
+pub(crate) fn measure(path: &Path, window: (f32, Option<f32>)) -> Result<Option<f32>, MixingError> {
+    let mut filters = trim_filters(window);
+    filters.push("loudnorm=print_format=json".to_owned());
+
+    let output = run(
+        Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(path)
+            .arg("-af")
+            .arg(filters.join(","))
+            .args(["-f", "null", "-"]),
+        path,
+    )?;
+
+    // the report is the last thing printed
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report = stderr
+        .rfind('{')
+        .and_then(|start| serde_json::from_str::<LoudnormReport>(&stderr[start..]).ok())
+        .ok_or_else(|| MixingError::Tool(path.to_owned(), "no loudnorm report".to_owned()))?;
+
+    // silence measures as -inf
+    Ok(report
+        .input_i
+        .parse::<f32>()
+        .ok()
+        .filter(|lufs| lufs.is_finite()))
+}
+
+#[derive(Debug, Default)]
+struct AudioStream {
+    codec: String,
+    sample_rate: Option<u32>,
+    bit_rate: Option<u32>,
+}
+
+/// Codec, bit rate and sample rate of the first audio stream of the video, `None` without audio.
+fn audio_stream(video: &Path) -> Result<Option<AudioStream>, MixingError> {
+    let output = run(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "a:0",
+                "-show_entries",
+                "stream=codec_name,sample_rate,bit_rate",
+                "-of",
+                "default=noprint_wrappers=1",
+            ])
+            .arg(video),
+        video,
+    )?;
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    let mut stream = AudioStream::default();
+    for line in output.lines() {
+        // containers which do not store the bit rate report N/A
+        match line.split_once('=') {
+            Some(("codec_name", codec)) => stream.codec = codec.trim().to_owned(),
+            Some(("sample_rate", rate)) => stream.sample_rate = rate.trim().parse().ok(),
+            Some(("bit_rate", rate)) => stream.bit_rate = rate.trim().parse().ok(),
+            _ => {}
+        }
+    }
+
+    Ok((!stream.codec.is_empty()).then_some(stream))
+}
+
+/// Runs the audio of the rendered video through a limiter keeping its true peak under the
+/// ceiling. The audio is oversampled to 192 kHz so peaks between the samples are caught too, and
+/// encoded again with the codec, bit rate and sample rate of the render. The other streams are
+/// copied and the container is kept.
+pub fn limit_true_peak(video: &Path, ceiling: f32) -> Result<(), MixingError> {
+    let Some(stream) = audio_stream(video)? else {
+        return Ok(());
+    };
+
+    let limited = video.with_extension(format!(
+        "limited.{}",
+        video.extension().unwrap_or_default().to_string_lossy()
+    ));
+    let limit = 10f32.powf(ceiling / 20.);
+    let sample_rate = stream.sample_rate.unwrap_or(48000);
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-y", "-v", "error", "-i"])
+        .arg(video)
+        .args(["-map", "0", "-c", "copy", "-c:a"])
+        .arg(&stream.codec);
+    if let Some(bit_rate) = stream.bit_rate {
+        command.arg("-b:a").arg(bit_rate.to_string());
+    }
+    command
+        .arg("-af")
+        .arg(format!(
+            "aresample=192000,alimiter=limit={limit:.4}:level=0,aresample={sample_rate}"
+        ))
+        .arg(&limited);
+
+    run(&mut command, &limited)?;
+
+    std::fs::rename(&limited, video).map_err(|e| MixingError::Io(video.to_owned(), e))
+}
@@ -1,38 +1,221 @@
-pub use fframes_renderer::{fframes_logger, render, render_backend, RenderOptions};
-use hello_world_example::upvotes_video::UpvotesVideo;
-
-fn main() {
-    // debug_frame(
-    //     317,
-    //     UpvotesVideo {},
-    //     "out.png",
-    //     RenderOptions {
-    //         media_dir: "./media",
-    //         logger: fframes_logger::FFramesLoggerVariant::Compact,
-    //         render_backend: render_backend::CpuRenderingBackend {
-    //             cache_capacity: 0,
-    //             ..Default::default()
-    //         },
-    //         preferred_codec: "libx264",
-    //         ..Default::default()
-    //     },
-    // )
-    // .unwrap();
-
-    render(
-        UpvotesVideo {},
-        "out.mp4",
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use fframes::Video;
+pub use fframes_renderer::{debug_frame, fframes_logger, render, render_backend, RenderOptions};
+use hello_world_example::{
+    alignment::{write_sidecars, Alignment},
+    assets::validate_media,
+    format::{Feed, Format, Landscape, Portrait, Square},
+    loudness::limit_true_peak,
+    mixing::{check_tools, prepare_audio, prepare_tracks},
+    story::Story,
+    upvotes_video::UpvotesVideo,
+};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Renders reddit thread stories into short videos")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render the whole video
+    Render(RenderArgs),
+    /// Render a single frame to a png or svg file for previewing
+    Frame(FrameArgs),
+    /// Check the story manifest and its media without rendering anything
+    Validate(StoryArgs),
+    /// Write the .vtt and .word.vtt subtitles of a narration from stable-ts alignment json
+    Subtitles(SubtitlesArgs),
+}
+
+#[derive(Args)]
+struct SubtitlesArgs {
+    /// Json written by `stable-ts <audio> -o <alignment>`
+    alignment: PathBuf,
+    /// Narration the subtitles belong to, they are written next to it
+    audio: PathBuf,
+}
+
+#[derive(Args)]
+struct StoryArgs {
+    /// Story manifest, either .json or .toml
+    #[arg(short, long, default_value = "stories/7.toml")]
+    manifest: PathBuf,
+    /// Directory all the audio, images, fonts and subtitles are resolved from
+    #[arg(long, default_value = "./media")]
+    media_dir: String,
+    /// Aspect ratio of the video
+    #[arg(long, value_enum, default_value_t = VideoFormat::Portrait)]
+    format: VideoFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VideoFormat {
+    /// 1080x1920, shorts and reels
+    Portrait,
+    /// 1920x1080, youtube
+    Landscape,
+    /// 1080x1080 feed post
+    Square,
+    /// 1080x1350 feed post
+    Feed,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    #[command(flatten)]
+    story: StoryArgs,
+    #[arg(short, long, default_value = "out.mp4")]
+    output: String,
+    #[arg(long, default_value = "libx265")]
+    codec: String,
+    /// Amount of frames rendered in parallel
+    #[arg(long, default_value_t = 6)]
+    concurrency: usize,
+}
+
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("position").required(true))]
+struct FrameArgs {
+    #[command(flatten)]
+    story: StoryArgs,
+    /// Output file, the format is picked from the extension
+    #[arg(short, long, default_value = "out.png")]
+    output: String,
+    /// Frame number from the start of the video
+    #[arg(long, group = "position")]
+    frame: Option<usize>,
+    /// Timestamp in seconds from the start of the video
+    #[arg(long, group = "position")]
+    at: Option<f32>,
+}
+
+impl StoryArgs {
+    fn load<F: Format>(&self) -> Result<UpvotesVideo<F>, Box<dyn std::error::Error>> {
+        let mut story = Story::from_file(&self.manifest)?;
+        story.load_words(&self.media_dir)?;
+
+        Ok(UpvotesVideo::new(story))
+    }
+
+    /// Loads the story and makes sure every media file it references is in place.
+    fn load_validated<F: Format>(&self) -> Result<UpvotesVideo<F>, Box<dyn std::error::Error>> {
+        let video = self.load::<F>()?;
+        let missing = validate_media(&video, &self.media_dir);
+
+        if !missing.is_empty() {
+            for missing_asset in &missing {
+                eprintln!("{missing_asset}");
+            }
+
+            return Err(format!("{} media files are missing or unreadable", missing.len()).into());
+        }
+
+        Ok(video)
+    }
+
+    fn render_options(&self) -> RenderOptions<'_> {
         RenderOptions {
-            media_dir: "./media",
+            media_dir: &self.media_dir,
             logger: fframes_logger::FFramesLoggerVariant::Compact,
             render_backend: render_backend::CpuRenderingBackend {
                 cache_capacity: 0,
-                concurrency: 6,
                 ..Default::default()
             },
-            preferred_codec: "libx265",
             ..Default::default()
-        },
-    )
-    .unwrap();
+        }
+    }
+}
+
+impl FrameArgs {
+    fn frame_number<F: Format>(&self) -> usize {
+        match (self.frame, self.at) {
+            (Some(frame), _) => frame,
+            (None, Some(at)) => (at * UpvotesVideo::<F>::FPS as f32).round() as usize,
+            (None, None) => unreachable!("clap requires one of --frame or --at"),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let command = Cli::parse().command;
+    let format = match &command {
+        Command::Render(args) => args.story.format,
+        Command::Frame(args) => args.story.format,
+        Command::Validate(args) => args.format,
+        Command::Subtitles(_) => VideoFormat::Portrait,
+    };
+
+    match format {
+        VideoFormat::Portrait => run::<Portrait>(command),
+        VideoFormat::Landscape => run::<Landscape>(command),
+        VideoFormat::Square => run::<Square>(command),
+        VideoFormat::Feed => run::<Feed>(command),
+    }
+}
+
+fn run<F: Format>(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Render(args) => {
+            check_tools()?;
+            let mut video = args.story.load_validated::<F>()?;
+            prepare_audio(&mut video, &args.story.media_dir)?;
+            let loudness = video.story.loudness;
+
+            render(
+                video,
+                &args.output,
+                RenderOptions {
+                    render_backend: render_backend::CpuRenderingBackend {
+                        cache_capacity: 0,
+                        concurrency: args.concurrency,
+                        ..Default::default()
+                    },
+                    preferred_codec: &args.codec,
+                    ..args.story.render_options()
+                },
+            )?;
+
+            if loudness.enabled {
+                limit_true_peak(args.output.as_ref(), loudness.true_peak)?;
+            }
+        }
+        Command::Frame(args) => {
+            if !args.output.ends_with(".png") && !args.output.ends_with(".svg") {
+                return Err(format!("{} should be a .png or .svg file", args.output).into());
+            }
+
+            // the trimmed narration changes the timeline, frames match the rendered video
+            check_tools()?;
+            let mut video = args.story.load_validated::<F>()?;
+            prepare_tracks(&mut video, &args.story.media_dir)?;
+            debug_frame(
+                args.frame_number::<F>(),
+                video,
+                &args.output,
+                args.story.render_options(),
+            )?;
+        }
+        Command::Validate(args) => {
+            let video = args.load_validated::<F>()?;
+
+            println!(
+                "{} is valid: {} comments, {} scenes",
+                args.manifest.display(),
+                video.story.comments.len(),
+                video.scenes().len()
+            );
+        }
+        Command::Subtitles(args) => {
+            let alignment = Alignment::from_file(&args.alignment)?;
+            let (sentences, words) = write_sidecars(&alignment, &args.audio)?;
+
+            println!("wrote {} and {}", sentences.display(), words.display());
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,336 @@
+/// Inline and block styles of a word of a comment body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
+    pub code: bool,
+    pub quote: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Inline {
+    Bold,
+    Italic,
+    Strikethrough,
+    Code,
+}
+
+impl Style {
+    fn flag(&mut self, inline: Inline) -> &mut bool {
+        match inline {
+            Inline::Bold => &mut self.bold,
+            Inline::Italic => &mut self.italic,
+            Inline::Strikethrough => &mut self.strikethrough,
+            Inline::Code => &mut self.code,
+        }
+    }
+
+    fn has(mut self, inline: Inline) -> bool {
+        *self.flag(inline)
+    }
+}
+
+/// Whitespace separated word of the comment body with the markup stripped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub style: Style,
+    /// List bullets and numbers, shown but not narrated.
+    pub decoration: bool,
+}
+
+/// Inline markers in the order they are matched, longer ones first.
+const MARKERS: [(&str, Inline); 6] = [
+    ("**", Inline::Bold),
+    ("__", Inline::Bold),
+    ("~~", Inline::Strikethrough),
+    ("*", Inline::Italic),
+    ("_", Inline::Italic),
+    ("`", Inline::Code),
+];
+
+/// Parses the reddit markdown subset used in comment bodies: `**bold**`, `*italics*`,
+/// `~~strikethrough~~`, `` `inline code` ``, `>` quotes and `-`, `*`, `+` or `1.` lists.
+///
+/// Text layout has no notion of paragraphs, so blocks are flattened into a single run of words
+/// and only keep their styling. Markers not opening or closing a word, or never closed on their
+/// line, are left as they are, so `2 * 3`, `snake_case` and `a*b` stay untouched.
+pub fn parse(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for line in source.lines() {
+        let mut line = line.trim();
+        let mut style = Style::default();
+
+        if let Some(quoted) = line.strip_prefix('>') {
+            style.quote = true;
+            line = quoted.trim_start();
+        }
+
+        if let Some((bullet, item)) = list_item(line) {
+            tokens.push(Token {
+                text: bullet.to_owned(),
+                style,
+                decoration: true,
+            });
+            line = item;
+        }
+
+        parse_inline(line, style, &mut tokens);
+    }
+
+    tokens
+}
+
+/// Splits a list item into its bullet and its text.
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+    {
+        return Some(("•", item.trim_start()));
+    }
+
+    let (number, item) = line.split_once(". ")?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+        .then(|| (&line[..number.len() + 1], item.trim_start()))
+}
+
+fn parse_inline(line: &str, mut style: Style, tokens: &mut Vec<Token>) {
+    let mut word = String::new();
+    let mut word_style = style;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                tokens.push(Token {
+                    text: std::mem::take(&mut word),
+                    style: word_style,
+                    decoration: false,
+                });
+            }
+
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if let Some(escaped) = rest.strip_prefix('\\').and_then(|r| r.chars().next()) {
+            if word.is_empty() {
+                word_style = style;
+            }
+
+            word.push(escaped);
+            rest = &rest[1 + escaped.len_utf8()..];
+            continue;
+        }
+
+        let toggled = MARKERS
+            .iter()
+            // nothing but the closing backtick is a marker inside inline code
+            .filter(|(marker, _)| !style.code || *marker == "`")
+            .find(|(marker, inline)| {
+                let Some(after) = rest.strip_prefix(marker) else {
+                    return false;
+                };
+
+                // underscores only count at the edges of a word
+                let intraword = marker.starts_with('_');
+                if style.has(*inline) {
+                    !(word.is_empty() || intraword && after.starts_with(char::is_alphanumeric))
+                } else {
+                    (word.is_empty() || !intraword)
+                        && after.starts_with(|c: char| !c.is_whitespace())
+                        && closed_later(after, marker)
+                }
+            });
+
+        match toggled {
+            Some((marker, inline)) => {
+                let flag = style.flag(*inline);
+                *flag = !*flag;
+                rest = &rest[marker.len()..];
+            }
+            None => {
+                if word.is_empty() {
+                    word_style = style;
+                }
+
+                word.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(Token {
+            text: word,
+            style: word_style,
+            decoration: false,
+        });
+    }
+}
+
+/// Whether the marker closes a word further on the line. Markers never closed are kept as text
+/// rather than styling the rest of the line.
+fn closed_later(after: &str, marker: &str) -> bool {
+    let intraword = marker.starts_with('_');
+    let mut previous: Option<char> = None;
+
+    for (index, c) in after.char_indices() {
+        if previous == Some('\\') {
+            previous = None;
+            continue;
+        }
+
+        let closing = &after[index..];
+        if let Some(following) = closing.strip_prefix(marker) {
+            let closes_word = previous.is_some_and(|previous| !previous.is_whitespace());
+            if closes_word && !(intraword && following.starts_with(char::is_alphanumeric)) {
+                return true;
+            }
+        }
+
+        previous = Some(c);
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: Style = Style {
+        bold: false,
+        italic: false,
+        strikethrough: false,
+        code: false,
+        quote: false,
+    };
+    const BOLD: Style = Style {
+        bold: true,
+        ..PLAIN
+    };
+    const ITALIC: Style = Style {
+        italic: true,
+        ..PLAIN
+    };
+    const STRIKETHROUGH: Style = Style {
+        strikethrough: true,
+        ..PLAIN
+    };
+    const CODE: Style = Style {
+        code: true,
+        ..PLAIN
+    };
+
+    fn words(source: &str) -> Vec<(String, Style)> {
+        parse(source)
+            .into_iter()
+            .map(|token| (token.text, token.style))
+            .collect()
+    }
+
+    fn word(text: &str, style: Style) -> (String, Style) {
+        (text.to_owned(), style)
+    }
+
+    #[test]
+    fn strips_closed_markers() {
+        assert_eq!(
+            words("a **bold** and *italic* ~~gone~~ `code`"),
+            vec![
+                word("a", PLAIN),
+                word("bold", BOLD),
+                word("and", PLAIN),
+                word("italic", ITALIC),
+                word("gone", STRIKETHROUGH),
+                word("code", CODE),
+            ]
+        );
+    }
+
+    #[test]
+    fn styles_every_word_between_the_markers() {
+        assert_eq!(
+            words("*one two* three"),
+            vec![
+                word("one", ITALIC),
+                word("two", ITALIC),
+                word("three", PLAIN)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unclosed_markers() {
+        assert_eq!(
+            words("a*b c d"),
+            vec![word("a*b", PLAIN), word("c", PLAIN), word("d", PLAIN)]
+        );
+        assert_eq!(
+            words("**bold rest"),
+            vec![word("**bold", PLAIN), word("rest", PLAIN)]
+        );
+        assert_eq!(
+            words("`open code"),
+            vec![word("`open", PLAIN), word("code", PLAIN)]
+        );
+    }
+
+    #[test]
+    fn closes_markers_on_their_own_line() {
+        assert_eq!(
+            words("*open\nclosed*"),
+            vec![word("*open", PLAIN), word("closed*", PLAIN)]
+        );
+    }
+
+    #[test]
+    fn keeps_markers_not_touching_a_word() {
+        assert_eq!(
+            words("2 * 3 snake_case"),
+            vec![
+                word("2", PLAIN),
+                word("*", PLAIN),
+                word("3", PLAIN),
+                word("snake_case", PLAIN)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_markers() {
+        assert_eq!(
+            words(r"\*not italic\*"),
+            vec![word("*not", PLAIN), word("italic*", PLAIN)]
+        );
+    }
+
+    #[test]
+    fn ignores_markers_inside_code() {
+        assert_eq!(words("`a*b*c`"), vec![word("a*b*c", CODE)]);
+    }
+
+    #[test]
+    fn flattens_quotes_and_lists() {
+        let tokens = parse("> quoted\n- item\n2. second");
+
+        assert!(tokens[0].style.quote);
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_str(), token.decoration))
+                .collect::<Vec<_>>(),
+            vec![
+                ("quoted", false),
+                ("•", true),
+                ("item", false),
+                ("2.", true),
+                ("second", false)
+            ]
+        );
+    }
+}
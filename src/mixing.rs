@@ -0,0 +1,653 @@
+use crate::analysis::AnalysisCache;
+use crate::assets::AssetKind;
+use crate::format::Format;
+use crate::loudness::Loudness;
+use crate::sfx::Cue;
+use crate::silence::{shift_subtitles, Silence};
+use crate::timing::layout_scenes;
+use crate::upvotes_video::{UpvotesVideo, BACKGROUND_MUSIC};
+use fframes::serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Formatter;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Directory of the media directory the mixed tracks are written to.
+pub const DERIVED_DIR: &str = ".derived";
+
+/// Subtitles of an audio file are named after it with these suffixes.
+const SUBTITLES_SUFFIXES: [&str; 2] = [".vtt", ".word.vtt"];
+
+/// Shortest attack and release, the envelope divides by them.
+const MIN_RAMP: f32 = 0.01;
+
+/// Level, fades and trim of an audio track. The tracks are processed into the derived directory
+/// ahead of the render, the audio maps only place the files on the timeline. The editor preview
+/// plays the files as they are.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "fframes::serde", default)]
+pub struct Mix {
+    pub volume: f32,
+    /// Seconds of the fade in from the start of the trimmed track.
+    pub fade_in: f32,
+    /// Seconds of the fade out up to the end of the trimmed track.
+    pub fade_out: f32,
+    /// Seconds skipped at the start of the file.
+    pub offset: f32,
+    /// Seconds played from the offset, the rest of the file when missing.
+    pub length: Option<f32>,
+    /// Integrated loudness the track is normalized to in LUFS, before its volume is applied.
+    /// The narration defaults to the `[loudness]` target.
+    pub loudness: Option<f32>,
+    /// Cuts the silence before the first and after the last sound louder than the `[silence]`
+    /// threshold, inside the offset and length. On for the narration unless `[silence]` is off.
+    pub trim_silence: Option<bool>,
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Self {
+            volume: 1.,
+            fade_in: 0.,
+            fade_out: 0.,
+            offset: 0.,
+            length: None,
+            loudness: None,
+            trim_silence: None,
+        }
+    }
+}
+
+impl Mix {
+    pub fn is_neutral(&self) -> bool {
+        let trim_silence = self.trim_silence.filter(|&trim| trim);
+
+        Mix {
+            trim_silence,
+            ..*self
+        } == Mix::default()
+    }
+
+    /// Seconds of the file the track plays, from the offset or the first sound up to the end of
+    /// the length or the last sound, keeping the `[silence]` lead around the sounds. The end is
+    /// `None` for the end of the file.
+    fn window(
+        &self,
+        media_dir: &Path,
+        file: &str,
+        silence: &Silence,
+        cache: &mut AnalysisCache,
+    ) -> Result<(f32, Option<f32>), MixingError> {
+        let start = self.offset;
+        let end = self.length.map(|length| self.offset + length);
+
+        if self.trim_silence != Some(true) {
+            return Ok((start, end));
+        }
+
+        let speech = cache.speech_bounds(media_dir, file, silence)?;
+
+        Ok(match speech {
+            Some((speech_start, speech_end)) => (
+                start.max(speech_start),
+                Some(end.map_or(speech_end, |end| end.min(speech_end))),
+            ),
+            None => (start, end),
+        })
+    }
+
+    /// ffmpeg filters applying the mix to the seconds of the file of the media directory given
+    /// by `window`.
+    fn filters(
+        &self,
+        media_dir: &Path,
+        file: &str,
+        (start, end): (f32, Option<f32>),
+        cache: &mut AnalysisCache,
+    ) -> Result<Vec<String>, MixingError> {
+        let mut filters = trim_filters((start, end));
+        let length = end.map(|end| (end - start).max(0.));
+
+        if let Some(target) = self.loudness {
+            // only what is played counts, the trimmed silence would lower the measurement
+            if let Some(integrated) = cache.integrated(media_dir, file, (start, end))? {
+                filters.push(format!("volume={:.2}dB", target - integrated));
+            }
+        }
+
+        if self.volume != 1. {
+            filters.push(format!("volume={:.3}", self.volume));
+        }
+
+        if self.fade_in > 0. {
+            filters.push(format!("afade=t=in:st=0:d={:.3}", self.fade_in));
+        }
+
+        if self.fade_out > 0. {
+            let length = match length {
+                Some(length) => length,
+                None => audio_length(&media_dir.join(file))? - start,
+            };
+
+            filters.push(format!(
+                "afade=t=out:st={:.3}:d={:.3}",
+                (length - self.fade_out).max(0.),
+                self.fade_out
+            ));
+        }
+
+        Ok(filters)
+    }
+}
+
+/// ffmpeg filters cutting the file down to the seconds given by `window`, none for the whole file.
+pub(crate) fn trim_filters((start, end): (f32, Option<f32>)) -> Vec<String> {
+    let length = end.map(|end| (end - start).max(0.));
+    if start <= 0. && length.is_none() {
+        return Vec::new();
+    }
+
+    let duration = length
+        .map(|length| format!(":duration={length:.3}"))
+        .unwrap_or_default();
+
+    vec![
+        format!("atrim=start={start:.3}{duration}"),
+        "asetpts=PTS-STARTPTS".to_owned(),
+    ]
+}
+
+/// Mix of every kind of track, set with the top level `[mix]` table of the manifest.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(crate = "fframes::serde", default)]
+pub struct Mixes {
+    pub narration: Mix,
+    pub music: Mix,
+    /// Sound effects of the cues, whichever file the sound pack plays for them.
+    #[serde(alias = "woosh")]
+    pub transition: Mix,
+    #[serde(alias = "click")]
+    pub upvote: Mix,
+    #[serde(alias = "awards")]
+    pub award: Mix,
+    pub pop: Mix,
+}
+
+impl Mixes {
+    pub fn cue(&self, cue: Cue) -> Mix {
+        match cue {
+            Cue::Transition => self.transition,
+            Cue::Upvote => self.upvote,
+            Cue::Award => self.award,
+            Cue::Pop => self.pop,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PreparedTrack {
+    file: String,
+    mix: Mix,
+    derived: String,
+    /// Seconds cut from the start of the file.
+    start: f32,
+}
+
+/// Picks the files the scenes play, the processed ones once `prepare_audio` wrote them.
+#[derive(Debug, Default)]
+pub struct Mixer {
+    pub mixes: Mixes,
+    pub silence: Silence,
+    /// Narration files mixed apart from the `[mix]` narration.
+    narration_mixes: Vec<(String, Mix)>,
+    prepared: Vec<PreparedTrack>,
+    /// Background music with its mix and the ducking applied.
+    music: Option<String>,
+}
+
+impl Mixer {
+    pub fn new(
+        mut mixes: Mixes,
+        narration_mixes: Vec<(String, Mix)>,
+        loudness: Loudness,
+        silence: Silence,
+    ) -> Self {
+        let narration = |mut mix: Mix| {
+            if loudness.enabled {
+                mix.loudness.get_or_insert(loudness.target);
+            }
+
+            if silence.trim {
+                mix.trim_silence.get_or_insert(true);
+            }
+
+            mix
+        };
+
+        mixes.narration = narration(mixes.narration);
+
+        Self {
+            mixes,
+            silence,
+            narration_mixes: narration_mixes
+                .into_iter()
+                .map(|(file, mix)| (file, narration(mix)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Mix of the narration file, its own one or the `[mix]` narration.
+    pub fn narration_mix(&self, file: &str) -> Mix {
+        self.narration_mixes
+            .iter()
+            .find(|(narration, _)| narration == file)
+            .map_or(self.mixes.narration, |(_, mix)| *mix)
+    }
+
+    fn prepared(&self, file: &str, mix: &Mix) -> Option<&PreparedTrack> {
+        self.prepared
+            .iter()
+            .find(|track| track.file == file && track.mix == *mix)
+    }
+
+    /// File played for the track, the original one until it is prepared.
+    pub fn file<'a>(&'a self, file: &'a str, mix: &Mix) -> &'a str {
+        self.prepared(file, mix)
+            .map_or(file, |track| track.derived.as_str())
+    }
+
+    /// Narration file played by the scene. Its subtitles are named after it too, they are
+    /// shifted along with the trimmed narration.
+    pub fn narration<'a>(&'a self, file: &'a str) -> &'a str {
+        self.file(file, &self.narration_mix(file))
+    }
+
+    /// Seconds cut from the start of the narration file, word timings are moved back by as much.
+    pub fn narration_start(&self, file: &str) -> f32 {
+        self.prepared(file, &self.narration_mix(file))
+            .map_or(0., |track| track.start)
+    }
+
+    pub fn music(&self) -> &str {
+        self.music.as_deref().unwrap_or(BACKGROUND_MUSIC)
+    }
+}
+
+/// Lowers the background music while any narration plays, set with the top level `[ducking]`
+/// table of the manifest. It is mixed into the music ahead of the render, the editor preview
+/// plays the music as it is.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "fframes::serde", default)]
+pub struct Ducking {
+    /// Gain of the music under the narration, `1` turns the ducking off.
+    pub gain: f32,
+    /// Seconds the music takes to go down, it is down by the time the narration starts.
+    pub attack: f32,
+    /// Seconds the music takes to come back up once the narration is over.
+    pub release: f32,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            gain: 0.25,
+            attack: 0.3,
+            release: 0.8,
+        }
+    }
+}
+
+impl Ducking {
+    /// Volume of the music over the time `t` in seconds as an ffmpeg expression. Every narration
+    /// window ramps the ducking in and out, overlapping ramps take the deepest one.
+    fn volume_expression(&self, windows: &[(f32, f32)]) -> String {
+        let attack = self.attack.max(MIN_RAMP);
+        let release = self.release.max(MIN_RAMP);
+
+        let ducked = windows
+            .iter()
+            .map(|(start, end)| {
+                format!(
+                    "clip((t-{:.3})/{attack:.3},0,1)*clip(({:.3}-t)/{release:.3},0,1)",
+                    start - attack,
+                    end + release,
+                )
+            })
+            .reduce(|deepest, window| format!("max({deepest},{window})"))
+            .unwrap_or_else(|| "0".to_owned());
+
+        format!("1-{:.3}*{ducked}", 1. - self.gain)
+    }
+}
+
+#[derive(Debug)]
+pub enum MixingError {
+    Io(PathBuf, std::io::Error),
+    /// A tool exited with an error, with what it printed.
+    Tool(PathBuf, String),
+    /// A tool needed to mix the audio could not be started.
+    MissingTool(&'static str, std::io::Error),
+}
+
+impl std::fmt::Display for MixingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MixingError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            MixingError::Tool(path, output) => write!(f, "{}: {}", path.display(), output.trim()),
+            MixingError::MissingTool(tool, e) => {
+                write!(
+                    f,
+                    "{tool} is needed to mix the audio, is ffmpeg installed? ({e})"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MixingError {}
+
+pub(crate) fn run(command: &mut Command, path: &Path) -> Result<Output, MixingError> {
+    let output = command
+        .output()
+        .map_err(|e| MixingError::Io(path.to_owned(), e))?;
+
+    if !output.status.success() {
+        return Err(MixingError::Tool(
+            path.to_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+
+    Ok(output)
+}
+
+/// Makes sure ffmpeg and ffprobe can be run before anything is rendered, every render measures
+/// and mixes its audio with them.
+pub fn check_tools() -> Result<(), MixingError> {
+    for tool in ["ffmpeg", "ffprobe"] {
+        Command::new(tool)
+            .arg("-version")
+            .output()
+            .map_err(|e| MixingError::MissingTool(tool, e))?;
+    }
+
+    Ok(())
+}
+
+/// Size and modification time of a file, derived files are made again once they change.
+pub(crate) fn source_stamp(path: &Path) -> Result<(u64, u64), MixingError> {
+    let metadata = std::fs::metadata(path).map_err(|e| MixingError::Io(path.to_owned(), e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+
+    Ok((metadata.len(), modified))
+}
+
+/// Length of an audio file in seconds, read with `ffprobe`.
+pub fn audio_length(path: &Path) -> Result<f32, MixingError> {
+    let output = run(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-show_entries",
+                "format=duration",
+                "-of",
+                "csv=p=0",
+            ])
+            .arg(path),
+        path,
+    )?;
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    output
+        .trim()
+        .parse()
+        .map_err(|_| MixingError::Tool(path.to_owned(), format!("unexpected duration {output}")))
+}
+
+/// Runs the file of the media directory through the filters into the derived directory. The
+/// processed file is named after the source and the filters, so it is only made once.
+fn process(media_dir: &Path, file: &str, filters: &[String]) -> Result<String, MixingError> {
+    let filters = filters.join(",");
+    let mut hasher = DefaultHasher::new();
+    (file, source_stamp(&media_dir.join(file))?, &filters).hash(&mut hasher);
+
+    let stem = Path::new(file).file_stem().unwrap_or_default();
+    let derived = Path::new(DERIVED_DIR)
+        .join(format!(
+            "{}.{:016x}.wav",
+            stem.to_string_lossy(),
+            hasher.finish()
+        ))
+        .to_string_lossy()
+        .into_owned();
+
+    let output = media_dir.join(&derived);
+    if output.is_file() {
+        return Ok(derived);
+    }
+
+    let derived_dir = media_dir.join(DERIVED_DIR);
+    std::fs::create_dir_all(&derived_dir).map_err(|e| MixingError::Io(derived_dir, e))?;
+
+    run(
+        Command::new("ffmpeg")
+            .args(["-y", "-v", "error", "-i"])
+            .arg(media_dir.join(file))
+            .arg("-af")
+            .arg(filters)
+            .arg(&output),
+        &output,
+    )?;
+
+    Ok(derived)
+}
+
+/// Writes every track with a mix to the derived directory along with its shifted subtitles, then
+/// the background music ducked under the narration of every scene, and makes the video play the
+/// processed files.
+pub fn prepare_audio<F: Format>(
+    video: &mut UpvotesVideo<F>,
+    media_dir: impl AsRef<Path>,
+) -> Result<(), MixingError> {
+    let media_dir = media_dir.as_ref();
+    let mut cache = AnalysisCache::load(media_dir);
+
+    mix_tracks(video, media_dir, &mut cache)?;
+    mix_music(video, media_dir, &mut cache)?;
+    cache.save()?;
+
+    remove_stale_derived(media_dir, &video.mixer)
+}
+
+/// Writes the tracks with a mix and their shifted subtitles only, enough for the timeline of the
+/// video to match the render. Single frames have no use for the music.
+pub fn prepare_tracks<F: Format>(
+    video: &mut UpvotesVideo<F>,
+    media_dir: impl AsRef<Path>,
+) -> Result<(), MixingError> {
+    let media_dir = media_dir.as_ref();
+    let mut cache = AnalysisCache::load(media_dir);
+
+    mix_tracks(video, media_dir, &mut cache)?;
+    cache.save()
+}
+
+fn mix_tracks<F: Format>(
+    video: &mut UpvotesVideo<F>,
+    media_dir: &Path,
+    cache: &mut AnalysisCache,
+) -> Result<(), MixingError> {
+    let assets = video
+        .scenes()
+        .iter()
+        .flat_map(|scene| scene.assets())
+        .collect::<Vec<_>>();
+
+    let mut prepared: Vec<PreparedTrack> = Vec::new();
+    for asset in assets {
+        let already_prepared = prepared
+            .iter()
+            .any(|track| track.file == asset.file && track.mix == asset.mix);
+        if asset.kind != AssetKind::Audio || asset.mix.is_neutral() || already_prepared {
+            continue;
+        }
+
+        let window = asset
+            .mix
+            .window(media_dir, &asset.file, &video.mixer.silence, cache)?;
+        let filters = asset.mix.filters(media_dir, &asset.file, window, cache)?;
+        let derived = process(media_dir, &asset.file, &filters)?;
+
+        for suffix in SUBTITLES_SUFFIXES {
+            let subtitles = media_dir.join(format!("{}{suffix}", asset.file));
+            if subtitles.is_file() {
+                let shifted = media_dir.join(format!("{derived}{suffix}"));
+                shift_subtitles(&subtitles, &shifted, window.0)?;
+            }
+        }
+
+        prepared.push(PreparedTrack {
+            file: asset.file,
+            mix: asset.mix,
+            derived,
+            start: window.0,
+        });
+    }
+    video.mixer.prepared = prepared;
+
+    Ok(())
+}
+
+/// Mixes the background music, ducked under the narration of the tracks mixed before.
+fn mix_music<F: Format>(
+    video: &mut UpvotesVideo<F>,
+    media_dir: &Path,
+    cache: &mut AnalysisCache,
+) -> Result<(), MixingError> {
+    // the narration is trimmed by now, so the music ducks under what is actually played
+    let (video_length, narration_windows) = {
+        let scenes = video.scenes();
+        let spans = layout_scenes(scenes.iter().map(|scene| scene.timing()), |narration| {
+            audio_length(&media_dir.join(narration))
+        })?;
+        let windows = spans
+            .iter()
+            .filter_map(|span| span.narration.map(|(_, end)| (span.start, end)))
+            .collect::<Vec<_>>();
+
+        (spans.last().map_or(0., |span| span.end), windows)
+    };
+
+    let music_mix = video.mixer.mixes.music;
+    let mut window = music_mix.window(media_dir, BACKGROUND_MUSIC, &video.mixer.silence, cache)?;
+    // the music fades out with the video rather than at the end of its file
+    if music_mix.fade_out > 0. {
+        let video_end = window.0 + video_length;
+        window.1 = Some(window.1.map_or(video_end, |end| end.min(video_end)));
+    }
+    let mut filters = music_mix.filters(media_dir, BACKGROUND_MUSIC, window, cache)?;
+
+    let ducking = video.story.ducking;
+    if ducking.gain < 1. {
+        filters.push(format!(
+            "volume=eval=frame:volume='{}'",
+            ducking.volume_expression(&narration_windows)
+        ));
+    }
+
+    if !filters.is_empty() {
+        video.mixer.music = Some(process(media_dir, BACKGROUND_MUSIC, &filters)?);
+    }
+
+    Ok(())
+}
+
+/// Removes the processed tracks and subtitles the video no longer plays, every change of a mix
+/// leaves the previous ones behind.
+fn remove_stale_derived(media_dir: &Path, mixer: &Mixer) -> Result<(), MixingError> {
+    let derived_dir = media_dir.join(DERIVED_DIR);
+    let Ok(entries) = std::fs::read_dir(&derived_dir) else {
+        return Ok(());
+    };
+
+    let played = mixer
+        .prepared
+        .iter()
+        .map(|track| track.derived.as_str())
+        .chain(mixer.music.as_deref())
+        .flat_map(|derived| {
+            std::iter::once(derived.to_owned())
+                .chain(SUBTITLES_SUFFIXES.map(|suffix| format!("{derived}{suffix}")))
+        })
+        .map(|derived| media_dir.join(derived))
+        .collect::<Vec<_>>();
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| MixingError::Io(derived_dir.clone(), e))?
+            .path();
+        // the analysis caches live next to the tracks
+        let name = path.to_string_lossy();
+        let is_track = name.ends_with(".wav") || name.ends_with(".vtt");
+
+        if is_track && !played.contains(&path) {
+            std::fs::remove_file(&path).map_err(|e| MixingError::Io(path.clone(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUCKING: Ducking = Ducking {
+        gain: 0.25,
+        attack: 0.5,
+        release: 1.,
+    };
+
+    #[test]
+    fn keeps_the_music_up_without_narration() {
+        assert_eq!(DUCKING.volume_expression(&[]), "1-0.750*0");
+    }
+
+    #[test]
+    fn ramps_down_before_the_narration_and_up_after_it() {
+        assert_eq!(
+            DUCKING.volume_expression(&[(2., 4.)]),
+            "1-0.750*clip((t-1.500)/0.500,0,1)*clip((5.000-t)/1.000,0,1)"
+        );
+    }
+
+    #[test]
+    fn takes_the_deepest_of_overlapping_windows() {
+        assert_eq!(
+            DUCKING.volume_expression(&[(2., 4.), (4.5, 6.)]),
+            "1-0.750*max(clip((t-1.500)/0.500,0,1)*clip((5.000-t)/1.000,0,1),\
+             clip((t-4.000)/0.500,0,1)*clip((7.000-t)/1.000,0,1))"
+        );
+    }
+
+    #[test]
+    fn never_divides_by_an_instant_ramp() {
+        let ducking = Ducking {
+            attack: 0.,
+            release: 0.,
+            ..DUCKING
+        };
+
+        assert_eq!(
+            ducking.volume_expression(&[(1., 2.)]),
+            "1-0.750*clip((t-0.990)/0.010,0,1)*clip((2.010-t)/0.010,0,1)"
+        );
+    }
+}
@@ -1,9 +1,26 @@
-use crate::shared::{upvote_arrow, TransitionTimelines, EXIT_DURATION};
+use crate::assets::{Asset, SceneAssets};
+use crate::author::{
+    author_items, flow_header, posted_item, Author, HeaderItem, HEADER_LINE_HEIGHT,
+};
+use crate::emoji;
+use crate::format::Canvas;
+use crate::highlight::{HighlightStyle, Transition, WordLook, WordState};
+use crate::locale::{Locale, TimeStyle};
+use crate::markdown::{self, Style};
+use crate::mixing::{Mix, Mixer};
+use crate::sfx::{cue_assets, cue_tracks, Cue, CueAt, CueTime, SceneCues, SoundPack};
+use crate::shared::{
+    current_second, measure_styled_text_width, measure_text_width, upvote_arrow,
+    TransitionTimelines, EXIT_DURATION, SPRING,
+};
+use crate::theme::Theme;
+use crate::timing::{SceneTiming, Timing};
 pub use fframes::{audio_data, fframes_context, frame, video::Video, Color};
 use fframes::{
-    serde::Deserialize, svgr, AnimateRuntimeInput, AnimationRuntime, AudioTimestamp, Easing, Scene,
-    SceneInfo, Svgr,
+    serde::Deserialize, svgr, AnimateRuntimeInput, AnimationRuntime, AudioTimestamp, Easing,
+    KeyFrame, KeyFramesAnimation, Scene, SceneInfo, Svgr,
 };
+use std::borrow::Cow;
 
 #[derive(Deserialize, Debug)]
 #[serde(crate = "fframes::serde")]
@@ -13,6 +30,8 @@ struct Word {
     end: f32,
 }
 
+/// Word level timings of the narration in the `[{word, start, end}]` format, an alternative to
+/// the `.word.vtt` subtitles.
 #[derive(Debug)]
 pub struct PostText {
     whole: String,
@@ -20,8 +39,8 @@ pub struct PostText {
 }
 
 impl PostText {
-    pub fn decode(file: &str) -> Self {
-        let words = serde_json::from_str::<Vec<Word>>(file).unwrap();
+    pub fn decode(file: &str) -> Result<Self, serde_json::Error> {
+        let words = serde_json::from_str::<Vec<Word>>(file)?;
         let whole = words
             .iter()
             .map(|w| w.word.as_str())
@@ -30,28 +49,495 @@ impl PostText {
             .trim()
             .to_owned();
 
-        Self { whole, words }
+        Ok(Self { whole, words })
+    }
+
+    /// Timings of the `.word.vtt` subtitles, one word per cue. Empty cues are skipped.
+    fn from_cues(cues: &[fframes::Cue]) -> Self {
+        let words = cues
+            .iter()
+            .filter(|cue| !cue.text.trim().is_empty())
+            .map(|cue| Word {
+                word: format!(" {}", cue.text.trim()),
+                start: cue.start,
+                end: cue.end,
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            whole: words
+                .iter()
+                .map(|w| w.word.as_str())
+                .collect::<String>()
+                .trim()
+                .to_owned(),
+            words,
+        }
+    }
+
+    /// Words of the latest sentences started by the given second that fit in `budget`
+    /// characters, the way the cue stack shows the subtitles. The sentence being narrated is
+    /// always shown whole.
+    fn cue_stack(&self, second: f32, budget: usize) -> std::ops::Range<usize> {
+        let started = self.words.iter().take_while(|w| w.start <= second).count();
+        if started == 0 {
+            return 0..0;
+        }
+
+        let ends_sentence = |index: &usize| {
+            self.words[*index]
+                .word
+                .trim_end()
+                .ends_with(['.', '!', '?'])
+        };
+        let sentence_start = |before: usize| {
+            (0..before)
+                .rev()
+                .find(ends_sentence)
+                .map_or(0, |index| index + 1)
+        };
+        let length = |words: std::ops::Range<usize>| {
+            self.words[words]
+                .iter()
+                .map(|w| w.word.len())
+                .sum::<usize>()
+        };
+
+        let end = (started - 1..self.words.len())
+            .find(ends_sentence)
+            .map_or(self.words.len(), |index| index + 1);
+        let mut start = sentence_start(started - 1);
+        while start > 0 {
+            let previous = sentence_start(start - 1);
+            if length(previous..end) > budget {
+                break;
+            }
+
+            start = previous;
+        }
+
+        start..end
+    }
+
+    /// Index of the word narrated at the given second. Pauses between the words keep the
+    /// previous one active.
+    fn word_at(&self, second: f32) -> Option<usize> {
+        let index = self.words.iter().rposition(|w| w.start <= second)?;
+
+        (index + 1 < self.words.len() || second < self.words[index].end).then_some(index)
+    }
+
+    /// Maps every whitespace separated token of the paragraph to the word it belongs to. Words
+    /// that do not start with a space are glued to the previous token.
+    fn token_word_indices(&self) -> Vec<usize> {
+        let mut tokens = Vec::new();
+
+        for (index, word) in self.words.iter().enumerate() {
+            let mut count = word.word.split_whitespace().count();
+            if !tokens.is_empty() && !word.word.starts_with(char::is_whitespace) {
+                count = count.saturating_sub(1);
+            }
+
+            tokens.extend(std::iter::repeat_n(index, count));
+        }
+
+        tokens
+    }
+
+    /// Tokens narrated by the given second. The current word counts by the part of it already
+    /// said, so the scroll following the narration moves on smoothly between the words.
+    fn spoken_tokens(
+        &self,
+        second: f32,
+        current_word: Option<usize>,
+        token_words: &[usize],
+    ) -> f32 {
+        let Some(current_word) = current_word else {
+            return match self.words.first() {
+                Some(first) if second >= first.start => token_words.len() as f32,
+                _ => 0.,
+            };
+        };
+
+        let before = token_words
+            .iter()
+            .position(|&word| word >= current_word)
+            .unwrap_or(token_words.len());
+        let own = token_words[before..]
+            .iter()
+            .take_while(|&&word| word == current_word)
+            .count();
+
+        let word = &self.words[current_word];
+        let said = match word.end > word.start {
+            true => ((second - word.start) / (word.end - word.start)).clamp(0., 1.),
+            false => 1.,
+        };
+
+        before as f32 + own as f32 * said
+    }
+}
+
+/// The narrated paragraph and the state of its per word highlight at the current frame.
+struct Karaoke {
+    text: String,
+    current_word: Option<usize>,
+    /// Second of the scene the current word started at.
+    current_word_start: Option<f32>,
+    /// Word of every whitespace separated token of the narration, `None` when they are the same.
+    token_words: Option<Vec<usize>>,
+    /// Narration token `text` starts with, the cue stack drops the cues that no longer fit.
+    first_token: usize,
+    /// Amount of tokens already narrated, fractional while the current word is being said.
+    spoken_tokens: f32,
+    /// Markdown styles of every token of `text`.
+    styles: Option<Vec<Style>>,
+}
+
+impl Karaoke {
+    fn word_of_token(&self, token: usize) -> usize {
+        let token = self.first_token + token;
+
+        self.token_words
+            .as_ref()
+            .and_then(|token_words| token_words.get(token).copied())
+            .unwrap_or(token)
+    }
+
+    fn word_state(&self, token: usize) -> WordState {
+        let word = self.word_of_token(token);
+
+        match self.current_word {
+            Some(current) if word == current => WordState::Current,
+            Some(current) if word > current => WordState::Upcoming {
+                distance: word - current,
+            },
+            Some(current) if word + 1 == current => WordState::Previous,
+            _ => WordState::Spoken,
+        }
+    }
+
+    fn style_of_token(&self, token: usize) -> Style {
+        self.styles
+            .as_ref()
+            .and_then(|styles| styles.get(token).copied())
+            .unwrap_or_default()
+    }
+
+    /// Shows the markdown body in place of the narration transcript. Words of the body are
+    /// matched to the narrated ones by their position, list bullets take the word following them.
+    fn with_markdown(self, body: &[markdown::Token]) -> Karaoke {
+        let shown_tokens =
+            self.first_token..self.first_token + self.text.split_whitespace().count();
+
+        let mut narrated = 0;
+        let tokens = body
+            .iter()
+            .map(|token| {
+                let index = narrated;
+                if !token.decoration {
+                    narrated += 1;
+                }
+
+                (token, index)
+            })
+            .filter(|(_, index)| shown_tokens.contains(index))
+            .collect::<Vec<_>>();
+
+        Karaoke {
+            text: tokens
+                .iter()
+                .map(|(token, _)| token.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            token_words: Some(
+                tokens
+                    .iter()
+                    .map(|(_, index)| self.word_of_token(index - self.first_token))
+                    .collect(),
+            ),
+            spoken_tokens: tokens
+                .iter()
+                .filter(|(_, index)| *index < self.spoken_tokens as usize)
+                .count() as f32
+                + self.spoken_tokens.fract(),
+            styles: Some(tokens.iter().map(|(token, _)| token.style).collect()),
+            first_token: 0,
+            current_word: self.current_word,
+            current_word_start: self.current_word_start,
+        }
+    }
+}
+
+/// How a token of the narration is drawn at the current frame.
+struct TokenLook<'a> {
+    look: WordLook,
+    style: Style,
+    /// Current word of the pop style, drawn again scaled over the line.
+    popped: bool,
+    /// Family, weight and style the token is drawn and measured with.
+    font: fframes::BreakLinesOpts<'a>,
+}
+
+impl TokenLook<'_> {
+    fn font_style(&self) -> &'static str {
+        match self.font.font_style {
+            fframes::FontStyle::Italic => "italic",
+            _ => "normal",
+        }
+    }
+}
+
+/// Current word placed on its laid out line.
+struct WordBox<'a> {
+    word: &'a str,
+    look: TokenLook<'a>,
+    x: f32,
+    baseline: f32,
+    width: f32,
+}
+
+/// How the comment body is laid out while it is being narrated.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum TextLayout {
+    /// Shows the latest subtitle cues that fit on the screen.
+    #[default]
+    CueStack,
+    /// Lays out the whole comment and scrolls it to keep the spoken line readable.
+    AutoScroll,
+}
+
+/// Comments deleted by their author or removed by the moderators keep their place in the thread
+/// and their narration, but lose the author and the body.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum CommentStatus {
+    #[default]
+    Visible,
+    Deleted,
+    Removed,
+}
+
+const DELETED_AUTHOR: &str = "[deleted]";
+const DELETED_AVATAR: &str = "avatar_deleted.png";
+
+impl CommentStatus {
+    pub(crate) fn is_visible(self) -> bool {
+        self == CommentStatus::Visible
+    }
+
+    fn avatar(self, avatar: &str) -> &str {
+        if self.is_visible() {
+            avatar
+        } else {
+            DELETED_AVATAR
+        }
+    }
+
+    /// Name replacing the author of hidden comments.
+    fn hidden_name(self) -> Option<&'static str> {
+        (!self.is_visible()).then_some(DELETED_AUTHOR)
+    }
+
+    fn body_placeholder(self) -> Option<&'static str> {
+        match self {
+            CommentStatus::Visible => None,
+            CommentStatus::Deleted => Some("[deleted]"),
+            CommentStatus::Removed => Some("[removed]"),
+        }
+    }
+}
+
+/// Already narrated comment of a reply chain, rendered collapsed above the reply being read.
+#[derive(Debug)]
+pub struct ParentComment<'a> {
+    pub author: &'a Author,
+    pub avatar: &'a str,
+    pub posted_ago: chrono::Duration,
+    pub upvote_count: u64,
+    pub status: CommentStatus,
+}
+
+/// Picks a single icon out of a horizontal strip of equally wide icons, like `awards.png`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(crate = "fframes::serde")]
+pub struct IconStrip {
+    pub index: usize,
+    pub len: usize,
+}
+
+/// Reddit award given to a comment, shown next to its author.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Award {
+    pub icon: String,
+    pub strip: Option<IconStrip>,
+    #[serde(default = "default_award_count")]
+    pub count: u64,
+    /// Played when the award pops in instead of the award sound of the sound pack.
+    pub sound: Option<String>,
+    /// Mix of the award sound instead of the `[mix]` award.
+    pub mix: Option<Mix>,
+}
+
+fn default_award_count() -> u64 {
+    1
+}
+
+/// Picture posted with the comment, shown under its text across the whole column.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Attachment {
+    #[serde(flatten)]
+    pub media: AttachmentMedia,
+    /// Size of the media in pixels, only its aspect ratio matters.
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde", untagged)]
+pub enum AttachmentMedia {
+    Image {
+        image: String,
+    },
+    /// Frames of a GIF exported to separate images, looped for the whole scene.
+    Animation {
+        frames: Vec<String>,
+        #[serde(default = "default_animation_fps")]
+        fps: f32,
+    },
+}
+
+fn default_animation_fps() -> f32 {
+    10.
+}
+
+impl Attachment {
+    fn files(&self) -> &[String] {
+        match &self.media {
+            AttachmentMedia::Image { image } => std::slice::from_ref(image),
+            AttachmentMedia::Animation { frames, .. } => frames,
+        }
+    }
+
+    /// Image shown at the given second of the scene.
+    fn image_at(&self, second: f32) -> Option<&str> {
+        let files = self.files();
+        let index = match self.media {
+            AttachmentMedia::Image { .. } => 0,
+            AttachmentMedia::Animation { fps, .. } => (second * fps) as usize % files.len().max(1),
+        };
+
+        files.get(index).map(String::as_str)
+    }
+
+    /// Size of the attachment scaled to the given width, or narrower when it would get taller
+    /// than `max_height`.
+    fn size_within(&self, width: usize, max_height: usize) -> (usize, usize) {
+        let height = width * self.height as usize / self.width.max(1) as usize;
+
+        match height > max_height {
+            true => (width * max_height / height, max_height),
+            false => (width, height),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Post<'a> {
-    pub title: &'a str,
+    pub author: &'a Author,
     pub audio_file: &'a str,
     pub avatar: &'a str,
     pub timelines: TransitionTimelines,
-    pub posted_when: &'a str,
-    pub upvote_count: &'a str,
-    pub replies_count: &'a str,
+    /// Age of the comment at the reference date of the video.
+    pub posted_ago: chrono::Duration,
+    pub upvote_count: u64,
+    /// Upvotes shown before the click, the counter ticks up from it to `upvote_count`.
+    pub upvote_count_from: u64,
+    pub replies_count: u64,
+    /// Comments this one replies to, from the top level one down to the direct parent.
+    pub parents: Vec<ParentComment<'a>>,
+    pub layout: TextLayout,
+    pub highlight_style: HighlightStyle,
+    /// Markdown source of the comment shown instead of the narration transcript.
+    pub body: Option<&'a str>,
+    pub status: CommentStatus,
+    pub awards: &'a [Award],
+    pub attachment: Option<&'a Attachment>,
+    /// Drives the paragraph and the highlight instead of the `.vtt` subtitles when provided.
+    pub words: Option<&'a PostText>,
+    pub canvas: Canvas,
+    pub theme: Theme,
+    pub locale: Locale,
+    pub mixer: &'a Mixer,
+    pub sounds: SoundPack<'a>,
 }
 
+/// The upvote is clicked this many frames before the end of the scene.
+const UPVOTE_LEAD_FRAMES: usize = 80;
+
+/// Horizontal shift of every nesting level of the reply chain.
+const REPLY_INDENT: usize = 70;
+/// Deepest reply the layout has room for, the collapsed parents take the rest of the screen.
+pub const MAX_REPLY_DEPTH: usize = 3;
+/// Narrowest the text column of a nested reply gets.
+const MIN_COLUMN_WIDTH: usize = 400;
+/// Vertical space taken by a collapsed parent comment on the portrait canvas.
+const PARENT_HEIGHT: usize = 240;
+/// Avatar, header and upvotes of a collapsed parent, shorter canvases do not squeeze them further.
+const MIN_PARENT_HEIGHT: usize = 170;
+const REPLY_ENTER_DURATION: f32 = 0.5;
+/// Space between the upvote count and the downvote arrow.
+const DOWNVOTE_GAP: f32 = 15.;
+const COUNT_UP_DURATION: f32 = 0.6;
+
+/// Awards pop in one after another once the comment has slid in.
+const AWARD_POP_FRAME: usize = 30;
+const AWARD_STAGGER_FRAMES: usize = 12;
+const AWARD_SIZE: usize = 44;
+/// Space between an award icon and its count.
+const AWARD_COUNT_GAP: f32 = 6.;
+
+/// Space around the current word inside the highlight pill.
+const PILL_PADDING: f32 = 12.;
+
+/// Space between the text and the attachment below it.
+const ATTACHMENT_GAP: usize = 40;
+const ATTACHMENT_RADIUS: usize = 24;
+/// Tallest attachment of the portrait canvas, taller ones are narrowed to keep the footer in view.
+const MAX_ATTACHMENT_HEIGHT: usize = 800;
+
+/// Center of the avatar, the header is aligned with it.
+const AVATAR_Y: usize = 260;
+const AVATAR_RADIUS: usize = 50;
+/// Top of the comment on the portrait canvas, other canvases move the comment with it.
+const COMMENT_TOP: usize = AVATAR_Y - AVATAR_RADIUS;
+
+/// Author headers start right of the avatar and wrap before getting wider than this.
+const HEADER_X: f32 = 220.;
+const HEADER_Y: f32 = AVATAR_Y as f32;
+const HEADER_WIDTH: usize = 780;
+
+/// The spoken line of an auto scrolled comment never goes below this point of the portrait canvas.
+const READING_BAND_BOTTOM: usize = 1100;
+/// Keeps the footer of auto scrolled comments clear of the screen edge.
+const VIEWPORT_BOTTOM_MARGIN: usize = 140;
+/// Auto scrolled text is clipped right under the author header.
+const VIEWPORT_TOP: usize = 310;
+/// Size of the subtitles cue stack fitting the portrait canvas.
+const CUE_STACK_BUDGET: usize = 750;
+/// Space of the portrait canvas not available for the cue stack: header, footer and margins.
+const CUE_STACK_RESERVED_HEIGHT: usize = 600;
+
 const BREAK_OPTS: fframes::BreakLinesOpts = fframes::BreakLinesOpts {
     width: 680,
     line_height: 1.3,
     font_size: 54,
-    font_family: "Noto Sans Medium",
+    font_family: Theme::LIGHT.fonts.medium,
     align: fframes::TextAlign::Left,
-    fill: "black",
+    fill: Theme::LIGHT.palette.text,
     font_stretch: fframes::FontStretch::Normal,
     font_style: fframes::FontStyle::Normal,
     font_weight: 500,
@@ -63,63 +549,67 @@ const BREAK_OPTS: fframes::BreakLinesOpts = fframes::BreakLinesOpts {
 
 impl Scene for Post<'_> {
     fn audio_map(&self, scene_info: &SceneInfo) -> fframes::AudioMap {
-        fframes::AudioMap::from_iter(
-            vec![
-                Some((
-                    self.audio_file,
-                    (AudioTimestamp::Second(0), AudioTimestamp::Eof),
-                )),
-                (scene_info.index != scene_info.total_scenes_in_video - 2).then_some((
-                    "woosh.mp3",
-                    (
-                        AudioTimestamp::Frame(scene_info.duration_in_frames - 20),
-                        AudioTimestamp::Eof,
-                    ),
-                )),
-                Some((
-                    "click.mp3",
-                    (
-                        AudioTimestamp::Frame(scene_info.duration_in_frames - 80),
-                        AudioTimestamp::Eof,
-                    ),
-                )),
-            ]
-            .into_iter()
-            .flatten(),
-        )
+        let narration = (
+            self.mixer.narration(self.audio_file),
+            (AudioTimestamp::Second(0), AudioTimestamp::Eof),
+        );
+        let cues = self.cues();
+
+        fframes::AudioMap::from_iter(std::iter::once(narration).chain(cue_tracks(
+            &cues,
+            &self.sounds,
+            self.mixer,
+            scene_info,
+        )))
     }
 
     fn overlap(&self) -> fframes::Overlap {
-        fframes::Overlap::Next(EXIT_DURATION)
+        self.timing().overlap()
     }
 
     fn duration(&self) -> fframes::Duration {
-        fframes::Duration::FromAudio(self.audio_file) + fframes::Duration::Seconds(1.0)
+        self.timing().duration()
     }
 
     fn render_frame(&self, mut frame: frame::Frame, ctx: &fframes_context::FFramesContext) -> Svgr {
-        let range_subtitles = ctx.get_subtitles(format!("{}.vtt", self.audio_file));
-        let word_subtitles = ctx.get_subtitles(format!("{}.word.vtt", self.audio_file));
-
-        let text_structure = frame.text_break_lines_strcuture(
-            ctx,
-            range_subtitles
-                .get_cue_stack(&frame, 750)
-                .into_iter()
-                .map(|c| c.text.as_str())
-                .fold(String::new(), |acc, s| acc + s)
-                .as_str(),
-            &BREAK_OPTS,
-        );
+        let karaoke = match (self.status.body_placeholder(), self.words) {
+            (Some(placeholder), _) => Karaoke {
+                text: placeholder.to_owned(),
+                current_word: None,
+                current_word_start: None,
+                token_words: None,
+                first_token: 0,
+                spoken_tokens: 0.,
+                styles: None,
+            },
+            (None, Some(words)) => words_karaoke(
+                words,
+                &frame,
+                self.mixer.narration_start(self.audio_file),
+                (self.layout == TextLayout::CueStack).then(|| self.cue_stack_budget()),
+            ),
+            (None, None) => self.subtitles_karaoke(&frame, ctx),
+        };
+        let karaoke = match self.body {
+            Some(body) if self.status.is_visible() => karaoke.with_markdown(&markdown::parse(body)),
+            _ => karaoke,
+        };
 
-        // for some reason the dot is not rendered correctly in the editor preview canvas
-        let delimiter = match ctx.mode {
-            fframes::FFramesMode::EditorTimelinePreview => " ",
-            _ => " · ",
+        let depth = self.parents.len();
+        let break_opts = fframes::BreakLinesOpts {
+            width: indented_width(BREAK_OPTS.width, depth),
+            font_family: self.theme.fonts.medium,
+            fill: self.theme.palette.text,
+            ..BREAK_OPTS
         };
 
+        let layout_text =
+            code_layout_text(&frame, ctx, &karaoke, self.theme.fonts.code, &break_opts);
+        let text_structure = emoji::break_lines(&frame, ctx, &layout_text, &break_opts);
+
         let scene_info = ctx.get_scene_info(self).unwrap();
-        let (translate_timeline, skew_timeline) = self.timelines.get(&frame, scene_info);
+        let (translate_timeline, skew_timeline) =
+            self.timelines.get(&frame, scene_info, &self.canvas);
 
         let translate_x = frame.animate(translate_timeline);
         let skew_x = skew_timeline
@@ -127,34 +617,95 @@ impl Scene for Post<'_> {
             .map(|tl| frame.animate(tl))
             .unwrap_or(0.);
 
-        let id = format!("avatar-{}", scene_info.index);
+        // replies slide up into their place under the parent when their narration starts
+        let (reply_offset, reply_opacity) = if depth > 0 {
+            let easing = fframes::Easing::Linear(REPLY_ENTER_DURATION);
+            let progress =
+                frame.animate(&fframes::KeyFramesAnimation::new(vec![fframes::KeyFrame {
+                    start: 0.,
+                    from: 0.,
+                    to: 1.,
+                    easing: &easing,
+                }]));
+
+            ((1. - progress) * 120., progress)
+        } else {
+            (0., 1.)
+        };
+
+        let mut header_items = author_items(
+            &frame,
+            ctx,
+            &self.theme,
+            self.author,
+            self.status.hidden_name(),
+        );
+        header_items.push(posted_item(
+            &frame,
+            ctx,
+            &self.theme,
+            author_delimiter(ctx),
+            self.locale.relative_time(self.posted_ago, TimeStyle::Short),
+        ));
+        header_items.extend(self.award_items(&mut frame, ctx));
+
+        let (header, header_lines) = flow_header(
+            header_items,
+            HEADER_X,
+            HEADER_Y,
+            indented_width(HEADER_WIDTH, depth) as f32,
+        );
+        // wrapped headers push the comment body down
+        let header_height = (header_lines - 1) as f32 * HEADER_LINE_HEIGHT;
+
+        let parents = self
+            .parents
+            .iter()
+            .enumerate()
+            .map(|(level, parent)| self.render_parent(&frame, ctx, scene_info, level, parent))
+            .collect::<Vec<_>>();
+
+        let reply_transform = format!(
+            "translate({}, {})",
+            depth * REPLY_INDENT,
+            depth * self.parent_height()
+        );
 
         fframes::svgr!(
-            <g transform={format!("translate({translate_x}, 0) skewX({skew_x})")}>
-                <circle
-                    cx="140"
-                    cy="260"
-                    r="50"
-                    fill={format!("url(#{})", id)}
-                />
-
-                 <pattern id={id} x="0%" y="0%" height="100%" width="100%" viewBox="0 0 100 100">
-                   <image x="0%" y="0%" width="100" height="100" href={ctx.get_image_link(self.avatar)} />
-                </pattern>
-
-                <text x="220" y="265" dominant-baseline="middle" font-size="40">
-                    {self.title}{delimiter}<tspan font-size="30" fill="#6b7280">{self.posted_when}</tspan>
-                </text>
+            <g transform={format!("translate({}, 0) skewX({skew_x})", translate_x + self.canvas.column_x() as f32)}>
+                <g transform={format!("translate(0, {})", self.top_offset())}>
+                    {parents}
 
-                {
-                    if let Some(text_structure) = text_structure {
-                        self.render_text(&frame, scene_info, &text_structure, word_subtitles)
-                    } else {
-                        Svgr::default()
-                    }
-                }
+                    <g transform={reply_transform}>
+                        <g transform={format!("translate(0, {reply_offset})")} opacity={reply_opacity}>
+                            {render_avatar(
+                                ctx,
+                                &format!("avatar-{}", scene_info.index),
+                                self.status.avatar(self.avatar),
+                            )}
+
+                            {header}
+
+                            {
+                                if let Some(text_structure) = text_structure {
+                                    self.render_text(
+                                        &frame,
+                                        ctx,
+                                        scene_info,
+                                        header_height,
+                                        &break_opts,
+                                        &text_structure,
+                                        &karaoke,
+                                    )
+                                } else {
+                                    Svgr::default()
+                                }
+                            }
+                        </g>
+                    </g>
+                </g>
 
-                {if scene_info.index == 1 {
+                {if self.timelines.is_revealed_by_bubble() {
                     svgr!(
                         <use href="#bubble" />
                         <use href="#test" />
@@ -167,39 +718,468 @@ impl Scene for Post<'_> {
     }
 }
 
+impl SceneTiming for Post<'_> {
+    fn timing(&self) -> Timing<'_> {
+        Timing {
+            narration: Some(self.mixer.narration(self.audio_file)),
+            tail: self.mixer.silence.pad,
+            // the next reply of the thread takes over the same layout
+            overlap: if self.timelines.exits() {
+                EXIT_DURATION
+            } else {
+                0.
+            },
+        }
+    }
+}
+
+impl SceneCues for Post<'_> {
+    fn cues(&self) -> Vec<CueAt<'_>> {
+        let mut cues = vec![CueAt::new(
+            Cue::Upvote,
+            CueTime::BeforeEnd(UPVOTE_LEAD_FRAMES),
+        )];
+
+        if !self.parents.is_empty() {
+            cues.push(CueAt::new(Cue::Pop, CueTime::Frame(0)));
+        }
+
+        cues.extend(self.timelines.exit_cue());
+        cues.extend(self.awards.iter().enumerate().map(|(index, award)| CueAt {
+            file: award.sound.as_deref(),
+            mix: award.mix,
+            ..CueAt::new(Cue::Award, CueTime::Frame(award_pop_frame(index)))
+        }));
+
+        cues
+    }
+}
+
+impl SceneAssets for Post<'_> {
+    fn assets(&self) -> Vec<Asset> {
+        let mut assets = vec![
+            Asset::track(self.audio_file, self.mixer.narration_mix(self.audio_file)),
+            Asset::image(self.status.avatar(self.avatar)),
+        ];
+        assets.extend(cue_assets(&self.cues(), &self.sounds, self.mixer));
+
+        for award in self.awards {
+            assets.push(Asset::image(&award.icon));
+        }
+
+        if let Some(attachment) = self.attachment {
+            assets.extend(attachment.files().iter().map(Asset::image));
+        }
+
+        let has_code = |body| markdown::parse(body).iter().any(|token| token.style.code);
+        if self.status.is_visible() && self.body.is_some_and(has_code) {
+            assets.push(Asset::font(self.theme.fonts.code_file));
+        }
+
+        // emoji of the subtitles are found when the media is validated
+        let shown_text = self.body.or(self.words.map(|words| words.whole.as_str()));
+        if self.status.is_visible() && shown_text.is_some_and(emoji::contains_emoji) {
+            assets.push(Asset::font(self.theme.fonts.emoji_file));
+        }
+
+        // word timings are read together with the manifest, only the subtitles are left to check
+        if self.words.is_none() && self.status.is_visible() {
+            assets.push(Asset::subtitles(format!("{}.vtt", self.audio_file)));
+            assets.push(Asset::subtitles(format!("{}.word.vtt", self.audio_file)));
+        }
+
+        assets
+    }
+}
+
+/// Separates the author from the age of the comment.
+fn author_delimiter(ctx: &fframes_context::FFramesContext) -> &'static str {
+    // for some reason the dot is not rendered correctly in the editor preview canvas
+    match ctx.mode {
+        fframes::FFramesMode::EditorTimelinePreview => " ",
+        _ => " · ",
+    }
+}
+
+fn render_avatar(ctx: &fframes_context::FFramesContext, id: &str, avatar: &str) -> Svgr {
+    fframes::svgr!(
+        <circle
+            cx="140"
+            cy={AVATAR_Y}
+            r={AVATAR_RADIUS}
+            fill={format!("url(#{})", id)}
+        />
+
+         <pattern id={id} x="0%" y="0%" height="100%" width="100%" viewBox="0 0 100 100">
+           <image x="0%" y="0%" width="100" height="100" href={ctx.get_image_link(avatar)} />
+        </pattern>
+    )
+}
+
+/// Text the lines are broken on. Code is drawn with the code font, every code word is measured as
+/// a run of digits of the text font about as wide as it. Keeps the words of the text in place.
+fn code_layout_text<'a>(
+    frame: &fframes::Frame,
+    ctx: &fframes_context::FFramesContext,
+    karaoke: &'a Karaoke,
+    code_font: &str,
+    break_opts: &fframes::BreakLinesOpts,
+) -> Cow<'a, str> {
+    let Some(styles) = karaoke
+        .styles
+        .as_ref()
+        .filter(|styles| styles.iter().any(|style| style.code))
+    else {
+        return Cow::Borrowed(&karaoke.text);
+    };
+
+    // the code font is monospaced, every character advances by the same width
+    let advance =
+        |font_family| measure_text_width(frame, ctx, "0", font_family, break_opts.font_size);
+    let text_advance = advance(break_opts.font_family);
+    let scale = match text_advance > 0. {
+        true => advance(code_font) / text_advance,
+        false => 1.,
+    };
+
+    karaoke
+        .text
+        .split_whitespace()
+        .enumerate()
+        .map(|(index, word)| match styles.get(index) {
+            Some(style) if style.code => {
+                let digits = (word.chars().count() as f32 * scale).round() as usize;
+                "0".repeat(digits.max(1))
+            }
+            _ => word.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .into()
+}
+
+/// Width left of a column indented to the given reply depth.
+fn indented_width(width: usize, depth: usize) -> usize {
+    width
+        .saturating_sub(depth * REPLY_INDENT)
+        .max(MIN_COLUMN_WIDTH)
+}
+
+fn award_pop_frame(index: usize) -> usize {
+    AWARD_POP_FRAME + index * AWARD_STAGGER_FRAMES
+}
+
+fn render_award_icon(ctx: &fframes_context::FFramesContext, award: &Award, x: f32, y: f32) -> Svgr {
+    let y = y - AWARD_SIZE as f32 / 2.;
+
+    match award.strip {
+        None => svgr!(
+            <image x={x} y={y} width={AWARD_SIZE} height={AWARD_SIZE} href={ctx.get_image_link(&award.icon)} />
+        ),
+        // every icon of the strip becomes a unit square of the viewBox
+        Some(strip) => svgr!(
+            <svg
+                x={x}
+                y={y}
+                width={AWARD_SIZE}
+                height={AWARD_SIZE}
+                viewBox={format!("{} 0 1 1", strip.index)}
+                preserveAspectRatio="none"
+            >
+                <image width={strip.len} height="1" preserveAspectRatio="none" href={ctx.get_image_link(&award.icon)} />
+            </svg>
+        ),
+    }
+}
+
+/// `trimmed` seconds were cut from the start of the narration, the word timings count from the
+/// start of the original file. `stack_budget` is set for the cue stack layout.
+fn words_karaoke(
+    words: &PostText,
+    frame: &fframes::Frame,
+    trimmed: f32,
+    stack_budget: Option<usize>,
+) -> Karaoke {
+    let second = current_second(frame) + trimmed;
+    let current_word = words.word_at(second);
+    let token_words = words.token_word_indices();
+
+    let (text, first_token) = match stack_budget {
+        Some(budget) => {
+            let shown = words.cue_stack(second, budget);
+            let text = words.words[shown.clone()]
+                .iter()
+                .map(|w| w.word.as_str())
+                .collect::<String>();
+            let first_token = token_words
+                .iter()
+                .position(|&word| word >= shown.start)
+                .unwrap_or(token_words.len());
+
+            (text.trim().to_owned(), first_token)
+        }
+        None => (words.whole.clone(), 0),
+    };
+
+    Karaoke {
+        text,
+        current_word,
+        current_word_start: current_word.map(|index| words.words[index].start - trimmed),
+        spoken_tokens: words.spoken_tokens(second, current_word, &token_words),
+        token_words: Some(token_words),
+        first_token,
+        styles: None,
+    }
+}
+
 impl Post<'_> {
-    fn render_text(
+    /// Comments keep the layout of the portrait design, moved up or down together with its top.
+    fn top_offset(&self) -> f32 {
+        self.canvas.scale_y(COMMENT_TOP) as f32 - COMMENT_TOP as f32
+    }
+
+    fn parent_height(&self) -> usize {
+        self.canvas.scale_y(PARENT_HEIGHT).max(MIN_PARENT_HEIGHT)
+    }
+
+    /// The cue stack is sized for the portrait canvas, shorter ones fit proportionally less text.
+    fn cue_stack_budget(&self) -> usize {
+        const PORTRAIT_HEIGHT: usize = 1920;
+
+        CUE_STACK_BUDGET * self.canvas.height.saturating_sub(CUE_STACK_RESERVED_HEIGHT)
+            / (PORTRAIT_HEIGHT - CUE_STACK_RESERVED_HEIGHT)
+    }
+
+    fn subtitles_karaoke(
         &self,
         frame: &fframes::Frame,
-        scene_info: &fframes::SceneInfo,
-        text_structure: &fframes::WrappedTextStructure,
-        word_subtitles: &fframes::Subtitles,
+        ctx: &fframes_context::FFramesContext,
+    ) -> Karaoke {
+        let narration = self.mixer.narration(self.audio_file);
+        let range_subtitles = ctx.get_subtitles(format!("{narration}.vtt"));
+        let word_subtitles = ctx.get_subtitles(format!("{narration}.word.vtt"));
+
+        // pauses between the word cues keep the previous word current, like the word timings do
+        let words = PostText::from_cues(&word_subtitles.cues);
+        let second = current_second(frame);
+        let current_word = words.word_at(second);
+        let token_words = words.token_word_indices();
+
+        let started_tokens = range_subtitles
+            .get_cue_stack(frame, usize::MAX)
+            .into_iter()
+            .map(|c| c.text.split_whitespace().count())
+            .sum::<usize>();
+
+        let text = match self.layout {
+            TextLayout::CueStack => range_subtitles
+                .get_cue_stack(frame, self.cue_stack_budget())
+                .into_iter()
+                .map(|c| c.text.as_str())
+                .fold(String::new(), |acc, s| acc + s),
+            TextLayout::AutoScroll => full_text(range_subtitles),
+        };
+
+        let first_token = match self.layout {
+            TextLayout::CueStack => started_tokens.saturating_sub(text.split_whitespace().count()),
+            TextLayout::AutoScroll => 0,
+        };
+
+        Karaoke {
+            text,
+            current_word,
+            current_word_start: current_word.map(|index| words.words[index].start),
+            spoken_tokens: words.spoken_tokens(second, current_word, &token_words),
+            token_words: Some(token_words),
+            first_token,
+            styles: None,
+        }
+    }
+
+    /// Awards following the author header, every award springs in on its own.
+    fn award_items<'a>(
+        &'a self,
+        frame: &mut fframes::Frame,
+        ctx: &'a fframes_context::FFramesContext,
+    ) -> Vec<HeaderItem<'a>> {
+        self.awards
+            .iter()
+            .enumerate()
+            .map(|(index, award)| {
+                let scale = frame.animate(&KeyFramesAnimation::new(vec![KeyFrame {
+                    start: frame.frame_to_second(award_pop_frame(index)),
+                    from: 0.,
+                    to: 1.,
+                    easing: &SPRING,
+                }]));
+
+                let count = (award.count > 1).then(|| self.locale.compact_count(award.count));
+                let count_width = count.as_ref().map_or(0., |count| {
+                    AWARD_COUNT_GAP + measure_text_width(frame, ctx, count, self.theme.fonts.body, 30)
+                });
+
+                HeaderItem::new(AWARD_SIZE as f32 + count_width, move |x, y| {
+                    let center = x + AWARD_SIZE as f32 / 2.;
+
+                    svgr!(
+                        <g transform={format!("translate({center}, {y}) scale({scale}) translate({}, {})", -center, -y)}>
+                            {render_award_icon(ctx, award, x, y)}
+
+                            {match &count {
+                                Some(count) => svgr!(
+                                    <text
+                                        x={x + AWARD_SIZE as f32 + AWARD_COUNT_GAP}
+                                        y={y + 5.}
+                                        dominant-baseline="middle"
+                                        font-size="30"
+                                        fill={self.theme.palette.muted_text}
+                                    >
+                                        {count.as_str()}
+                                    </text>
+                                ),
+                                None => Svgr::default(),
+                            }}
+                        </g>
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn render_parent(
+        &self,
+        frame: &fframes::Frame,
+        ctx: &fframes_context::FFramesContext,
+        scene_info: &SceneInfo,
+        level: usize,
+        parent: &ParentComment,
     ) -> Svgr {
-        let current_word_index = word_subtitles
-            .get_cue_with_index_for_frame(frame)
+        let mut header_items = author_items(
+            frame,
+            ctx,
+            &self.theme,
+            parent.author,
+            parent.status.hidden_name(),
+        );
+        header_items.push(posted_item(
+            frame,
+            ctx,
+            &self.theme,
+            author_delimiter(ctx),
+            self.locale
+                .relative_time(parent.posted_ago, TimeStyle::Short),
+        ));
+
+        // collapsed parents keep a single line, badges not fitting it are left out
+        let mut header_width = 0.;
+        let header_items = header_items
             .into_iter()
-            .next()
-            .map(|(index, _)| index);
+            .take_while(|item| {
+                header_width += item.width();
+                header_width <= indented_width(HEADER_WIDTH, level + 1) as f32
+            })
+            .collect();
+        let (header, _) = flow_header(header_items, HEADER_X, HEADER_Y, f32::INFINITY);
+
+        let x = level * REPLY_INDENT;
+        let y = level * self.parent_height();
+
+        // curved connector from the parent avatar down to the avatar of its reply
+        let child_avatar_y = AVATAR_Y + self.parent_height();
+        let connector = format!(
+            "M140 {} V{} Q140 {child_avatar_y} {} {child_avatar_y} H{}",
+            AVATAR_Y + AVATAR_RADIUS,
+            child_avatar_y - 30,
+            140 + 30,
+            140 + REPLY_INDENT - 50,
+        );
+
+        fframes::svgr!(
+            <g transform={format!("translate({x}, {y})")}>
+                <path d={connector} fill="none" stroke={self.theme.palette.connector} stroke-width="7" />
+
+                {render_avatar(
+                    ctx,
+                    &format!("avatar-{}-parent-{level}", scene_info.index),
+                    parent.status.avatar(parent.avatar),
+                )}
+
+                {header}
+
+                <g fill={self.theme.votes.upvoted} stroke={self.theme.votes.upvoted}>
+                    {upvote_arrow(200, 300, 0)}
+                </g>
+
+                <text x="280" y="345" font-family={self.theme.fonts.medium} dominant-baseline="middle" font-size="40">
+                    {self.locale.compact_count(parent.upvote_count)}
+                </text>
+            </g>
+        )
+    }
+
+    /// Counts up to the final upvotes while the upvote click plays, easing out towards the end.
+    fn animated_upvote_count(&self, frame: &fframes::Frame, on_second: f32) -> u64 {
+        let progress = frame.animate_runtime(AnimateRuntimeInput {
+            on_second,
+            from: 0f32,
+            to: 1.,
+            animation_runtime: &AnimationRuntime::Linear(COUNT_UP_DURATION),
+        });
+
+        let eased = 1. - (1. - progress.clamp(0., 1.)).powi(3);
+        let from = self.upvote_count_from.min(self.upvote_count);
 
+        from + ((self.upvote_count - from) as f64 * eased as f64).round() as u64
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_text(
+        &self,
+        frame: &fframes::Frame,
+        ctx: &fframes_context::FFramesContext,
+        scene_info: &SceneInfo,
+        header_height: f32,
+        break_opts: &fframes::BreakLinesOpts,
+        text_structure: &fframes::WrappedTextStructure,
+        karaoke: &Karaoke,
+    ) -> Svgr {
+        let Theme {
+            palette,
+            fonts,
+            votes,
+            ..
+        } = self.theme;
+        let transition = Transition::new(frame, karaoke.current_word_start);
+        // the laid out words stand in for the emoji, the words are taken from the text itself
+        let mut words = karaoke.text.split_whitespace();
         let mut flatten_word_index = 0;
         let lines = text_structure
             .lines
             .iter()
             .map(|line| {
                 fframes::svgr!(
-                    <tspan x={BREAK_OPTS.x} y={BREAK_OPTS.y} dx={line.dx} dy={line.dy.to_string()}>
-                        {line.words.iter().map(|word| {
-                            let (weight, fill) = match current_word_index {
-                                Some(current_word_index) if flatten_word_index == current_word_index  => (900, "#dc2626"),
-                                Some(current_word_index) if flatten_word_index > current_word_index => (400, "#4b5563"),
-                                _ => (400, "black")
+                    <tspan x={break_opts.x} y={break_opts.y} dx={line.dx} dy={line.dy.to_string()}>
+                        {line.words.iter().filter_map(|_| words.next()).map(|word| {
+                            let token = self.token_look(karaoke, flatten_word_index, transition, break_opts);
+                            // the popping word is drawn scaled over the line, its place stays empty
+                            let opacity = match token.popped {
+                                true => 0.,
+                                false => token.look.opacity,
                             };
 
                             flatten_word_index += 1;
 
                             fframes::svgr!(
-                                <tspan font-weight={weight} fill={fill}>
-                                   {word.as_str()}{" "}
+                                <tspan
+                                    font-weight={token.font.font_weight}
+                                    font-style={token.font_style()}
+                                    text-decoration={if token.style.strikethrough { "line-through" } else { "none" }}
+                                    font-family={token.font.font_family}
+                                    font-size={break_opts.font_size}
+                                    fill={token.look.fill}
+                                    fill-opacity={opacity}
+                                >
+                                   {emoji::word(word, fonts.emoji)}{" "}
                                 </tspan>
                             )
                         }).collect::<Vec<_>>()}
@@ -207,64 +1187,474 @@ impl Post<'_> {
                 )
             })
             .collect::<Vec<_>>();
+        let current_words =
+            self.current_word_boxes(frame, ctx, break_opts, text_structure, karaoke, transition);
 
         let start = 290;
-        let end = start
-            + (lines.len() as f32 * BREAK_OPTS.line_height * BREAK_OPTS.font_size as f32) as usize
-            - BREAK_OPTS.font_size;
+        let text_end = start
+            + (lines.len() as f32 * break_opts.line_height * break_opts.font_size as f32) as usize
+            - break_opts.font_size;
+
+        // the footer moves down under the attachment
+        let attachment = self
+            .attachment
+            .filter(|_| self.status.is_visible())
+            .map(|attachment| {
+                self.render_attachment(
+                    frame,
+                    ctx,
+                    scene_info,
+                    attachment,
+                    break_opts,
+                    text_end + ATTACHMENT_GAP,
+                )
+            });
+        let end = match attachment {
+            Some((_, height)) => text_end + ATTACHMENT_GAP + height,
+            None => text_end,
+        };
 
-        let on_second = frame.frame_to_second(scene_info.duration_in_frames - 80);
+        let on_second = frame.frame_to_second(scene_info.duration_in_frames - UPVOTE_LEAD_FRAMES);
 
         let upvote_fill = frame.animate_runtime(AnimateRuntimeInput {
             on_second,
-            from: Color::hex("#fff"),
-            to: Color::hex("#fe4500"),
+            from: Color::hex(votes.idle_fill),
+            to: Color::hex(votes.upvoted),
             animation_runtime: &AnimationRuntime::Linear(0.2),
         });
 
         let upvote_stroke = frame.animate_runtime(AnimateRuntimeInput {
             on_second,
-            from: Color::hex("#888a8c"),
-            to: Color::hex("#fe4500"),
+            from: Color::hex(votes.idle_stroke),
+            to: Color::hex(votes.upvoted),
             animation_runtime: &AnimationRuntime::Linear(0.2),
         });
 
-        let downvote_arrow_offset = match self.upvote_count.len() {
-            4 => 30,
-            5 => 55,
-            count => count * 11,
+        // the label goes through the compact formatter on every frame so the suffix switches
+        // at the same counts as for the static labels (999 -> 1k -> 1.1k)
+        let upvote_count = self
+            .locale
+            .compact_count(self.animated_upvote_count(frame, on_second));
+        let downvote_arrow_x =
+            280. + measure_text_width(frame, ctx, &upvote_count, fonts.medium, 40) + DOWNVOTE_GAP;
+
+        let scroll = match self.layout {
+            TextLayout::CueStack => 0.,
+            TextLayout::AutoScroll => {
+                let y_offset = self.top_offset()
+                    + (self.parents.len() * self.parent_height()) as f32
+                    + header_height;
+                auto_scroll_offset(
+                    &self.canvas,
+                    text_structure,
+                    break_opts,
+                    karaoke.spoken_tokens,
+                    y_offset,
+                    y_offset + (end + 240) as f32,
+                )
+            }
         };
 
-        fframes::svgr!(
-            <line x1="140" x2="140" y1={start + 20} y2={end + 140} stroke="#EDEFF1" stroke-width="7" />
-
-            <text
-              x={BREAK_OPTS.x}
-              y={BREAK_OPTS.y}
-              fill={BREAK_OPTS.fill}
-              font-size={BREAK_OPTS.font_size}
-              font-family={BREAK_OPTS.font_family}
-              font-weight={BREAK_OPTS.font_weight}
-             >
-                {lines}
-            </text>
-
-            <g fill={upvote_fill} stroke={upvote_stroke}>
-                {upvote_arrow(200, end + 90, 0)}
-            </g>
+        let body = fframes::svgr!(
+            <g transform={format!("translate(0, {})", -scroll)}>
+                <line x1="140" x2="140" y1={start + 20} y2={end + 140} stroke={palette.connector} stroke-width="7" />
+
+                {self.highlight_decorations(&current_words, break_opts, transition)}
+
+                <text
+                  x={break_opts.x}
+                  y={break_opts.y}
+                  fill={break_opts.fill}
+                  font-size={break_opts.font_size}
+                  font-family={break_opts.font_family}
+                  font-weight={break_opts.font_weight}
+                 >
+                    {lines}
+                </text>
+
+                {self.popped_words(&current_words, break_opts)}
+
+                {attachment.map(|(attachment, _)| attachment).unwrap_or_default()}
 
-            <text x="280" y={end + 135} font-family="Noto Sans Medium" dominant-baseline="middle" font-size="40">
-                {self.upvote_count}
-            </text>
-            <g fill="none" stroke="#888a8c">
-                {upvote_arrow(340 + downvote_arrow_offset, end + 94, 180)}
+                <g fill={upvote_fill} stroke={upvote_stroke}>
+                    {upvote_arrow(200, end + 90, 0)}
+                </g>
+
+                <text x="280" y={end + 135} font-family={fonts.medium} dominant-baseline="middle" font-size="40">
+                    {upvote_count}
+                </text>
+                <g fill="none" stroke={votes.idle_stroke}>
+                    {upvote_arrow(downvote_arrow_x.round() as usize, end + 94, 180)}
+                </g>
+
+                <text x="130" y={end + 210} dominant-baseline="middle" font-size="40" fill={palette.footer}>
+                    {self.locale.compact_count(self.replies_count)}
+                    " more replies"
+                </text>
             </g>
+        );
 
-            <text x="130" y={end + 210} dominant-baseline="middle" font-size="40" fill="#747677">
-                {self.replies_count}
-                " more replies"
-            </text>
+        // only the scrolling text can reach past the viewport
+        let body = match self.layout {
+            TextLayout::CueStack => body,
+            TextLayout::AutoScroll => {
+                let clip_id = format!("scroll-clip-{}", scene_info.index);
 
+                fframes::svgr!(
+                    <g>
+                        <clipPath id={clip_id.as_str()}>
+                            <rect x="0" y={VIEWPORT_TOP} width="100%" height="100%" />
+                        </clipPath>
+
+                        <g clip-path={format!("url(#{clip_id})")}>
+                            {body}
+                        </g>
+                    </g>
+                )
+            }
+        };
+
+        fframes::svgr!(
+            <g transform={format!("translate(0, {header_height})")}>
+                {body}
+            </g>
         )
     }
+
+    /// Look of a token of the narration and the font it is drawn with, shared by the text and
+    /// the highlight drawn around it.
+    fn token_look<'a>(
+        &self,
+        karaoke: &Karaoke,
+        token: usize,
+        transition: Transition,
+        break_opts: &fframes::BreakLinesOpts<'a>,
+    ) -> TokenLook<'a> {
+        let Theme {
+            palette,
+            fonts,
+            highlight,
+            ..
+        } = self.theme;
+        let style = karaoke.style_of_token(token);
+        let spoken = if style.quote || !self.status.is_visible() {
+            palette.muted_text
+        } else {
+            highlight.spoken
+        };
+        let state = match self.status.is_visible() {
+            true => karaoke.word_state(token),
+            false => WordState::Spoken,
+        };
+        let look = self
+            .highlight_style
+            .word_look(&self.theme, state, spoken, transition);
+        let weight = match style.bold {
+            true => look.weight.max(700.),
+            false => look.weight,
+        };
+
+        TokenLook {
+            look,
+            style,
+            popped: self.highlight_style == HighlightStyle::Pop && state == WordState::Current,
+            font: fframes::BreakLinesOpts {
+                font_family: if style.code {
+                    fonts.code
+                } else {
+                    break_opts.font_family
+                },
+                font_weight: weight.round() as usize,
+                font_style: if style.italic || style.quote {
+                    fframes::FontStyle::Italic
+                } else {
+                    fframes::FontStyle::Normal
+                },
+                ..*break_opts
+            },
+        }
+    }
+
+    /// Current words of the laid out lines, measured word by word in the fonts they are drawn
+    /// with. Only the styles drawing around the current word need them.
+    fn current_word_boxes<'a>(
+        &self,
+        frame: &fframes::Frame,
+        ctx: &fframes_context::FFramesContext,
+        break_opts: &fframes::BreakLinesOpts<'a>,
+        text_structure: &fframes::WrappedTextStructure,
+        karaoke: &'a Karaoke,
+        transition: Transition,
+    ) -> Vec<WordBox<'a>> {
+        if !self.status.is_visible()
+            || !matches!(
+                self.highlight_style,
+                HighlightStyle::Pill | HighlightStyle::Underline | HighlightStyle::Pop
+            )
+        {
+            return Vec::new();
+        }
+
+        let measure = |text: &str, font: &fframes::BreakLinesOpts| {
+            measure_styled_text_width(frame, ctx, text, font)
+        };
+        let space = measure("0 0", break_opts) - 2. * measure("0", break_opts);
+        let x = break_opts.x.parse::<f32>().unwrap_or_default();
+        let y = break_opts.y.parse::<f32>().unwrap_or_default();
+
+        let mut words = karaoke.text.split_whitespace();
+        let mut token = 0;
+        let mut boxes = Vec::new();
+        for line in &text_structure.lines {
+            let line_tokens = token..token + line.words.len();
+            if !line_tokens
+                .clone()
+                .any(|token| karaoke.word_state(token) == WordState::Current)
+            {
+                words.by_ref().take(line.words.len()).for_each(drop);
+                token = line_tokens.end;
+                continue;
+            }
+
+            let mut word_x = x + line.dx;
+            for (laid_out, word) in line.words.iter().zip(words.by_ref()) {
+                let look = self.token_look(karaoke, token, transition, break_opts);
+                // emoji are measured as their placeholders, the emoji font has no metrics here
+                let width = match emoji::contains_emoji(word) {
+                    true => measure(laid_out, break_opts),
+                    false => measure(word, &look.font),
+                };
+
+                if karaoke.word_state(token) == WordState::Current {
+                    boxes.push(WordBox {
+                        word,
+                        look,
+                        x: word_x,
+                        baseline: y + line.dy,
+                        width,
+                    });
+                }
+
+                word_x += width + space;
+                token += 1;
+            }
+        }
+
+        boxes
+    }
+
+    /// Pill or underline drawn under the current word.
+    fn highlight_decorations(
+        &self,
+        current_words: &[WordBox],
+        break_opts: &fframes::BreakLinesOpts,
+        transition: Transition,
+    ) -> Vec<Svgr> {
+        let font_size = break_opts.font_size as f32;
+        let entrance = transition.entrance();
+
+        current_words
+            .iter()
+            .filter_map(|word| match self.highlight_style {
+                HighlightStyle::Pill => Some(fframes::svgr!(
+                    <rect
+                        x={word.x - PILL_PADDING}
+                        y={word.baseline - font_size * 0.85}
+                        width={word.width + PILL_PADDING * 2.}
+                        height={font_size * 1.1}
+                        rx={PILL_PADDING}
+                        fill={self.theme.highlight.current}
+                        opacity={entrance}
+                    />
+                )),
+                HighlightStyle::Underline => Some(fframes::svgr!(
+                    <rect
+                        x={word.x}
+                        y={word.baseline + font_size * 0.12}
+                        width={word.width * entrance}
+                        height={font_size * 0.08}
+                        fill={self.theme.highlight.current}
+                    />
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The current word of the pop style, scaled around its center over the line.
+    fn popped_words(
+        &self,
+        current_words: &[WordBox],
+        break_opts: &fframes::BreakLinesOpts,
+    ) -> Vec<Svgr> {
+        current_words
+            .iter()
+            .filter(|word| word.look.popped)
+            .map(|word| {
+                let center_x = word.x + word.width / 2.;
+                let transform = format!(
+                    "translate({center_x} {baseline}) scale({scale}) translate({} {})",
+                    -center_x,
+                    -word.baseline,
+                    baseline = word.baseline,
+                    scale = word.look.look.font_scale,
+                );
+
+                fframes::svgr!(
+                    <text
+                        x={word.x}
+                        y={word.baseline}
+                        transform={transform}
+                        font-size={break_opts.font_size}
+                        font-family={word.look.font.font_family}
+                        font-weight={word.look.font.font_weight}
+                        font-style={word.look.font_style()}
+                        fill={word.look.look.fill}
+                        fill-opacity={word.look.look.opacity}
+                    >
+                        {emoji::word(word.word, self.theme.fonts.emoji)}
+                    </text>
+                )
+            })
+            .collect()
+    }
+
+    /// Attachment clipped to a rounded rect as wide as the text column, with its height.
+    fn render_attachment(
+        &self,
+        frame: &fframes::Frame,
+        ctx: &fframes_context::FFramesContext,
+        scene_info: &SceneInfo,
+        attachment: &Attachment,
+        break_opts: &fframes::BreakLinesOpts,
+        y: usize,
+    ) -> (Svgr, usize) {
+        let (width, height) =
+            attachment.size_within(break_opts.width, self.canvas.scale_y(MAX_ATTACHMENT_HEIGHT));
+        let id = format!("attachment-{}", scene_info.index);
+
+        let Some(image) = attachment.image_at(current_second(frame)) else {
+            return (Svgr::default(), height);
+        };
+
+        let svgr = fframes::svgr!(
+            <rect
+                x={break_opts.x}
+                y={y}
+                width={width}
+                height={height}
+                rx={ATTACHMENT_RADIUS}
+                fill={format!("url(#{id})")}
+            />
+
+            <pattern id={id.as_str()} x="0%" y="0%" height="100%" width="100%" viewBox={format!("0 0 {width} {height}")}>
+                <image x="0" y="0" width={width} height={height} href={ctx.get_image_link(image)} />
+            </pattern>
+        );
+
+        (svgr, height)
+    }
+}
+
+/// Joins every cue of the subtitles, not only the ones already spoken.
+fn full_text(subtitles: &fframes::Subtitles) -> String {
+    subtitles.cues.iter().map(|cue| cue.text.as_str()).collect()
+}
+
+/// Vertical offset keeping the line with the spoken word above the reading band. Words move the
+/// content by a fraction of the line so scrolling follows the narration instead of jumping.
+fn auto_scroll_offset(
+    canvas: &Canvas,
+    text_structure: &fframes::WrappedTextStructure,
+    break_opts: &fframes::BreakLinesOpts,
+    spoken_words: f32,
+    y_offset: f32,
+    content_bottom: f32,
+) -> f32 {
+    let line_pitch = break_opts.line_height * break_opts.font_size as f32;
+
+    let mut words_before = 0.;
+    let mut spoken_line = text_structure.lines.len() as f32;
+    for (index, line) in text_structure.lines.iter().enumerate() {
+        let words = line.words.len() as f32;
+        if spoken_words < words_before + words {
+            spoken_line = index as f32 + (spoken_words - words_before) / words;
+            break;
+        }
+
+        words_before += words;
+    }
+
+    let text_top = y_offset + break_opts.y.parse::<f32>().unwrap_or_default();
+    let spoken_y = text_top + spoken_line * line_pitch;
+    let viewport_bottom = canvas.height.saturating_sub(VIEWPORT_BOTTOM_MARGIN) as f32;
+    let max_scroll = (content_bottom - viewport_bottom).max(0.);
+
+    (spoken_y - canvas.scale_y(READING_BAND_BOTTOM) as f32).clamp(0., max_scroll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Words starting a second apart and lasting `length` seconds.
+    fn text(words: &[&str], length: f32) -> PostText {
+        let words = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| Word {
+                word: word.to_string(),
+                start: index as f32,
+                end: index as f32 + length,
+            })
+            .collect();
+
+        PostText {
+            whole: String::new(),
+            words,
+        }
+    }
+
+    #[test]
+    fn maps_tokens_to_their_words() {
+        let text = text(&[" Hello", " world,", "!", " two words"], 1.);
+
+        // the exclamation mark is glued to the token of the word before it
+        assert_eq!(text.token_word_indices(), vec![0, 1, 3, 3]);
+    }
+
+    #[test]
+    fn keeps_the_previous_word_between_words() {
+        let text = text(&[" one", " two"], 0.5);
+
+        assert_eq!(text.word_at(-0.1), None);
+        assert_eq!(text.word_at(0.2), Some(0));
+        assert_eq!(text.word_at(0.7), Some(0));
+        assert_eq!(text.word_at(1.2), Some(1));
+        assert_eq!(text.word_at(1.7), None);
+    }
+
+    #[test]
+    fn stacks_the_latest_sentences_within_the_budget() {
+        let text = text(&[" One", " two.", " Three.", " Four", " five", " six."], 1.);
+
+        assert_eq!(text.cue_stack(-1., 100), 0..0);
+        // the current sentence is shown whole, even past the budget
+        assert_eq!(text.cue_stack(3.5, 5), 3..6);
+        assert_eq!(text.cue_stack(3.5, 21), 3..6);
+        assert_eq!(text.cue_stack(3.5, 22), 2..6);
+        assert_eq!(text.cue_stack(3.5, 100), 0..6);
+        assert_eq!(text.cue_stack(0.5, 100), 0..2);
+    }
+
+    #[test]
+    fn counts_the_spoken_part_of_the_current_word() {
+        let text = text(&[" one", " two three"], 1.);
+        let token_words = text.token_word_indices();
+
+        assert_eq!(text.spoken_tokens(0.5, Some(0), &token_words), 0.5);
+        assert_eq!(text.spoken_tokens(1.5, Some(1), &token_words), 2.);
+        assert_eq!(text.spoken_tokens(5., None, &token_words), 3.);
+        assert_eq!(text.spoken_tokens(-1., None, &token_words), 0.);
+    }
 }
@@ -0,0 +1,155 @@
+use crate::assets::Asset;
+use crate::mixing::{Mix, Mixer};
+use fframes::serde::Deserialize;
+use fframes::{AudioTimestamp, SceneInfo};
+
+/// Sound effects the scenes play along their animations.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum Cue {
+    /// A comment sliding out of the screen.
+    Transition,
+    /// The upvote click at the end of every comment.
+    Upvote,
+    /// An award popping in next to the author.
+    Award,
+    /// The bubble of the intro and the outro springing, and replies entering under their parent.
+    Pop,
+}
+
+/// Files of the cues, `None` keeps the cue silent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundPack<'a> {
+    pub transition: Option<&'a str>,
+    pub upvote: Option<&'a str>,
+    pub award: Option<&'a str>,
+    pub pop: Option<&'a str>,
+}
+
+impl<'a> SoundPack<'a> {
+    pub const CLASSIC: SoundPack<'static> = SoundPack {
+        transition: Some("woosh.mp3"),
+        upvote: Some("click.mp3"),
+        award: None,
+        pop: None,
+    };
+
+    pub fn file(&self, cue: Cue) -> Option<&'a str> {
+        match cue {
+            Cue::Transition => self.transition,
+            Cue::Upvote => self.upvote,
+            Cue::Award => self.award,
+            Cue::Pop => self.pop,
+        }
+    }
+}
+
+/// Replaces the files of the theme's sound pack, set with the top level `[sounds]` table of the
+/// manifest. An empty file name silences the cue.
+#[derive(Deserialize, Debug, Default)]
+#[serde(crate = "fframes::serde")]
+pub struct Sounds {
+    pub transition: Option<String>,
+    pub upvote: Option<String>,
+    pub award: Option<String>,
+    pub pop: Option<String>,
+}
+
+impl Sounds {
+    pub fn apply<'a>(&'a self, pack: SoundPack<'a>) -> SoundPack<'a> {
+        let pick = |file: &'a Option<String>, default| match file.as_deref() {
+            Some("") => None,
+            Some(file) => Some(file),
+            None => default,
+        };
+
+        SoundPack {
+            transition: pick(&self.transition, pack.transition),
+            upvote: pick(&self.upvote, pack.upvote),
+            award: pick(&self.award, pack.award),
+            pop: pick(&self.pop, pack.pop),
+        }
+    }
+}
+
+/// Moment of the scene a cue plays at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueTime {
+    /// Frames from the start of the scene.
+    Frame(usize),
+    /// Frames before the end of the scene.
+    BeforeEnd(usize),
+}
+
+impl CueTime {
+    fn frame(self, scene_info: &SceneInfo) -> usize {
+        match self {
+            CueTime::Frame(frame) => frame,
+            CueTime::BeforeEnd(frames) => scene_info.duration_in_frames.saturating_sub(frames),
+        }
+    }
+}
+
+/// Sound effect emitted by an animation of the scene, timed with the same constants as the
+/// animation itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CueAt<'a> {
+    pub cue: Cue,
+    pub time: CueTime,
+    /// Plays instead of the file of the sound pack.
+    pub file: Option<&'a str>,
+    /// Mixes the cue instead of the `[mix]` of its kind.
+    pub mix: Option<Mix>,
+}
+
+impl<'a> CueAt<'a> {
+    pub fn new(cue: Cue, time: CueTime) -> Self {
+        Self {
+            cue,
+            time,
+            file: None,
+            mix: None,
+        }
+    }
+
+    fn file(&self, sounds: &SoundPack<'a>) -> Option<&'a str> {
+        self.file.or_else(|| sounds.file(self.cue))
+    }
+
+    fn mix(&self, mixer: &Mixer) -> Mix {
+        self.mix.unwrap_or_else(|| mixer.mixes.cue(self.cue))
+    }
+}
+
+/// Lists the sound effects of the scene, both its `audio_map` and its `assets` are made from them.
+pub trait SceneCues {
+    fn cues(&self) -> Vec<CueAt<'_>>;
+}
+
+/// Audio map entries of the cues with a sound, played until the end of their file.
+pub fn cue_tracks<'a>(
+    cues: &[CueAt<'a>],
+    sounds: &SoundPack<'a>,
+    mixer: &'a Mixer,
+    scene_info: &SceneInfo,
+) -> Vec<(&'a str, (AudioTimestamp, AudioTimestamp))> {
+    cues.iter()
+        .filter_map(|cue| {
+            let file = mixer.file(cue.file(sounds)?, &cue.mix(mixer));
+
+            Some((
+                file,
+                (
+                    AudioTimestamp::Frame(cue.time.frame(scene_info)),
+                    AudioTimestamp::Eof,
+                ),
+            ))
+        })
+        .collect()
+}
+
+pub fn cue_assets<'a>(cues: &[CueAt<'a>], sounds: &SoundPack<'a>, mixer: &Mixer) -> Vec<Asset> {
+    cues.iter()
+        .filter_map(|cue| Some(Asset::track(cue.file(sounds)?, cue.mix(mixer))))
+        .collect()
+}
@@ -1,3 +1,5 @@
+use crate::format::Canvas;
+use crate::sfx::{Cue, CueAt, CueTime};
 use fframes::{self, KeyFramesAnimation};
 use fframes::{KeyFrame, Svgr};
 use once_cell::sync::OnceCell;
@@ -15,25 +17,135 @@ pub fn upvote_arrow(x: usize, y: usize, rotate: usize) -> Svgr {
     )
 }
 
+/// Seconds passed since the start of the scene.
+pub fn current_second(frame: &fframes::Frame) -> f32 {
+    frame.index as f32 / FPS as f32
+}
+
+/// Width of a single line of text. Text layout only exposes the alignment offset of the lines, so
+/// the text is right aligned in a box wide enough to never wrap it.
+pub fn measure_text_width(
+    frame: &fframes::Frame,
+    ctx: &fframes::FFramesContext,
+    text: &str,
+    font_family: &str,
+    font_size: usize,
+) -> f32 {
+    let font = fframes::BreakLinesOpts {
+        font_family,
+        font_size,
+        ..Default::default()
+    };
+
+    measure_styled_text_width(frame, ctx, text, &font)
+}
+
+/// Same as `measure_text_width` with the family, size, weight and style of the given options.
+pub fn measure_styled_text_width(
+    frame: &fframes::Frame,
+    ctx: &fframes::FFramesContext,
+    text: &str,
+    font: &fframes::BreakLinesOpts,
+) -> f32 {
+    const BOX_WIDTH: usize = 10_000;
+
+    let font_size = font.font_size;
+    let opts = fframes::BreakLinesOpts {
+        width: BOX_WIDTH,
+        align: fframes::TextAlign::Right,
+        x: "0",
+        y: "0",
+        ..*font
+    };
+
+    frame
+        .text_break_lines_strcuture(ctx, text, &opts)
+        .and_then(|structure| {
+            structure
+                .lines
+                .first()
+                .map(|line| BOX_WIDTH as f32 - line.dx)
+        })
+        // the font is not loaded, guess from the average glyph width
+        .unwrap_or(text.chars().count() as f32 * font_size as f32 * 0.55)
+}
+
+/// Frame rate of every format.
+pub const FPS: usize = 60;
+
+/// Frames lasting the given seconds, to time audio to the animations.
+pub fn seconds_to_frames(seconds: f32) -> usize {
+    (seconds * FPS as f32).round() as usize
+}
+
 pub const EXIT_DURATION: f32 = 0.3;
-pub struct TransitionTimelines(
-    OnceCell<(KeyFramesAnimation<f32>, Option<KeyFramesAnimation<f32>>)>,
-);
+/// Overshooting spring of the bubble and of everything popping in.
+pub const SPRING: fframes::Easing = fframes::Easing::Spring2(1.0, 80., 16.);
+pub struct TransitionTimelines {
+    timelines: OnceCell<(KeyFramesAnimation<f32>, Option<KeyFramesAnimation<f32>>)>,
+    enters: bool,
+    exits: bool,
+    revealed_by_bubble: bool,
+    covered_by_bubble: bool,
+}
 
 impl TransitionTimelines {
     pub fn init() -> Self {
-        Self(OnceCell::new())
+        Self::new(true, true)
+    }
+
+    /// Scenes narrating a reply chain slide in with the first comment and out with the last one,
+    /// everything in between stays in place.
+    pub fn new(enters: bool, exits: bool) -> Self {
+        Self {
+            timelines: OnceCell::new(),
+            enters,
+            exits,
+            revealed_by_bubble: false,
+            covered_by_bubble: false,
+        }
+    }
+
+    /// The scene right after the bubble intro is uncovered by the shrinking bubble instead of
+    /// sliding in.
+    pub fn revealed_by_bubble(mut self) -> Self {
+        self.revealed_by_bubble = true;
+        self
+    }
+
+    /// The scene right before the bubble outro is covered by the growing bubble, its exit is
+    /// not heard.
+    pub fn covered_by_bubble(mut self) -> Self {
+        self.covered_by_bubble = true;
+        self
+    }
+
+    pub fn exits(&self) -> bool {
+        self.exits
+    }
+
+    pub fn is_revealed_by_bubble(&self) -> bool {
+        self.revealed_by_bubble
+    }
+
+    /// Transition sound played with the exit keyframe.
+    pub fn exit_cue(&self) -> Option<CueAt<'static>> {
+        (self.exits && !self.covered_by_bubble).then_some(CueAt::new(
+            Cue::Transition,
+            CueTime::BeforeEnd(seconds_to_frames(EXIT_DURATION)),
+        ))
     }
 
     pub fn get(
         &self,
         frame: &fframes::Frame,
         scene_info: &fframes::SceneInfo,
+        canvas: &Canvas,
     ) -> &(KeyFramesAnimation<f32>, Option<KeyFramesAnimation<f32>>) {
-        self.0.get_or_init(|| {
+        self.timelines.get_or_init(|| {
             let easing = fframes::Easing::Linear(EXIT_DURATION);
             let enter_keyframe = KeyFrame {
-                from: 1080.,
+                from: canvas.width as f32,
                 to: 0.,
                 start: 0.,
                 easing: &easing,
@@ -42,25 +154,34 @@ impl TransitionTimelines {
             let exit_keyframe = KeyFrame {
                 start: frame.frame_to_second(scene_info.duration_in_frames) - EXIT_DURATION,
                 from: 0.,
-                to: -1080.,
+                to: -(canvas.width as f32),
                 easing: &easing,
             };
 
-            let translate_timeline = KeyFramesAnimation::new(if scene_info.index == 1 {
-                vec![exit_keyframe]
-            } else if scene_info.is_last {
-                vec![enter_keyframe]
-            } else {
-                vec![enter_keyframe, exit_keyframe]
-            });
+            let enters = self.enters && !self.revealed_by_bubble;
+            let exits = self.exits && !scene_info.is_last;
 
-            let skew_timeline =
-                (scene_info.index != 1).then_some(KeyFramesAnimation::new(vec![KeyFrame {
-                    start: frame.frame_to_second(scene_info.duration_in_frames) - EXIT_DURATION,
+            let translate_timeline = KeyFramesAnimation::new(match (enters, exits) {
+                (true, true) => vec![enter_keyframe, exit_keyframe],
+                (true, false) => vec![enter_keyframe],
+                (false, true) => vec![exit_keyframe],
+                (false, false) => vec![KeyFrame {
+                    start: 0.,
                     from: 0.,
-                    to: 10.,
+                    to: 0.,
                     easing: &easing,
-                }]));
+                }],
+            });
+
+            let skew_timeline =
+                (exits && !self.revealed_by_bubble).then_some(KeyFramesAnimation::new(vec![
+                    KeyFrame {
+                        start: frame.frame_to_second(scene_info.duration_in_frames) - EXIT_DURATION,
+                        from: 0.,
+                        to: 10.,
+                        easing: &easing,
+                    },
+                ]));
 
             (translate_timeline, skew_timeline)
         })
@@ -0,0 +1,165 @@
+use crate::alignment::vtt_timestamp;
+use crate::mixing::{audio_length, run, MixingError};
+use fframes::serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+/// Trimming of the silence the text to speech leaves around the narration, set with the top level
+/// `[silence]` table of the manifest.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "fframes::serde", default)]
+pub struct Silence {
+    /// Keeps the narration files as they are when off.
+    pub trim: bool,
+    /// Level under which the narration counts as silent, in dBFS.
+    pub threshold: f32,
+    /// Seconds of the shortest silence trimmed, quieter breaths and clicks are kept.
+    pub min_duration: f32,
+    /// Seconds of the silence kept before the first and after the last word, so breaths and
+    /// soft consonants under the threshold are not cut.
+    pub lead: f32,
+    /// Seconds the comments and the title card go on after the last word of their narration.
+    pub pad: f32,
+    /// Seconds the title bubble goes on after the last word of the title, before it shrinks
+    /// into the title card.
+    pub bubble_pad: f32,
+}
+
+impl Default for Silence {
+    fn default() -> Self {
+        Self {
+            trim: true,
+            threshold: -50.,
+            min_duration: 0.1,
+            lead: 0.05,
+            pad: 1.,
+            bubble_pad: 0.4,
+        }
+    }
+}
+
+/// Seconds of the file between the first and the last sample louder than the threshold, widened
+/// by the lead within the file. `None` when the whole file is silent. Found with ffmpeg's
+/// `silencedetect`.
+pub fn speech_bounds(path: &Path, silence: &Silence) -> Result<Option<(f32, f32)>, MixingError> {
+    let Silence {
+        threshold,
+        min_duration,
+        lead,
+        ..
+    } = *silence;
+    let output = run(
+        Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(path)
+            .arg("-af")
+            .arg(format!(
+                "silencedetect=noise={threshold:.1}dB:d={min_duration:.3}"
+            ))
+            .args(["-f", "null", "-"]),
+        path,
+    )?;
+    let length = audio_length(path)?;
+
+    // every silence prints its start, and its end unless it lasts until the end of the file
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut silences: Vec<(f32, f32)> = Vec::new();
+    for line in stderr.lines() {
+        if let Some(start) = reported_second(line, "silence_start:") {
+            silences.push((start, length));
+        } else if let Some(end) = reported_second(line, "silence_end:") {
+            if let Some(silence) = silences.last_mut() {
+                silence.1 = end;
+            }
+        }
+    }
+
+    let start = match silences.first() {
+        Some(&(start, end)) if start <= 0. => end,
+        _ => 0.,
+    };
+    let end = match silences.last() {
+        Some(&(start, end)) if end >= length => start,
+        _ => length,
+    };
+
+    Ok((start < end).then_some(((start - lead).max(0.), (end + lead).min(length))))
+}
+
+fn reported_second(line: &str, key: &str) -> Option<f32> {
+    let (_, value) = line.split_once(key)?;
+
+    value.split_whitespace().next()?.parse().ok()
+}
+
+/// Writes the WebVTT subtitles with every cue moved `seconds` earlier, for the narration trimmed
+/// by as much. Cues moved before the start begin at the start.
+pub fn shift_subtitles(source: &Path, target: &Path, seconds: f32) -> Result<(), MixingError> {
+    let subtitles =
+        std::fs::read_to_string(source).map_err(|e| MixingError::Io(source.to_owned(), e))?;
+
+    let shifted = subtitles
+        .lines()
+        .map(|line| shift_cue_timing(line, seconds).unwrap_or_else(|| line.to_owned()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    std::fs::write(target, shifted + "\n").map_err(|e| MixingError::Io(target.to_owned(), e))
+}
+
+/// `None` for lines other than the `start --> end settings` timing of a cue.
+fn shift_cue_timing(line: &str, seconds: f32) -> Option<String> {
+    let (start, rest) = line.split_once("-->")?;
+    let rest = rest.trim_start();
+    let (end, settings) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+
+    let shift =
+        |timestamp: &str| parse_timestamp(timestamp.trim()).map(|at| vtt_timestamp(at - seconds));
+
+    Some(format!("{} --> {}{settings}", shift(start)?, shift(end)?))
+}
+
+/// Seconds of a `hh:mm:ss.ttt` or `mm:ss.ttt` timestamp.
+fn parse_timestamp(timestamp: &str) -> Option<f32> {
+    timestamp.split(':').try_fold(0., |seconds, part| {
+        Some(seconds * 60. + part.parse::<f32>().ok()?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps_with_and_without_hours() {
+        assert_eq!(parse_timestamp("00:01:02.500"), Some(62.5));
+        assert_eq!(parse_timestamp("01:02.500"), Some(62.5));
+        assert_eq!(parse_timestamp("1:xx.000"), None);
+    }
+
+    #[test]
+    fn shifts_cue_timings_keeping_the_settings() {
+        assert_eq!(
+            shift_cue_timing("00:00:01.500 --> 00:00:02.250 align:start", 0.5),
+            Some("00:00:01.000 --> 00:00:01.750 align:start".to_owned())
+        );
+        assert_eq!(
+            shift_cue_timing("00:01.500 --> 00:02.250", 0.25),
+            Some("00:00:01.250 --> 00:00:02.000".to_owned())
+        );
+    }
+
+    #[test]
+    fn starts_cues_moved_before_the_start_at_the_start() {
+        assert_eq!(
+            shift_cue_timing("00:00:00.100 --> 00:00:00.900", 0.3),
+            Some("00:00:00.000 --> 00:00:00.600".to_owned())
+        );
+    }
+
+    #[test]
+    fn leaves_other_lines_alone() {
+        assert_eq!(shift_cue_timing("WEBVTT", 1.), None);
+        assert_eq!(shift_cue_timing("a sentence of the narration", 1.), None);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::author::Author;
+use crate::highlight::HighlightStyle;
+use crate::locale::Locale;
+use crate::loudness::Loudness;
+use crate::mixing::{Ducking, Mix, Mixer, Mixes};
+use crate::post::{
+    Attachment, AttachmentMedia, Award, CommentStatus, IconStrip, PostText, TextLayout,
+    MAX_REPLY_DEPTH,
+};
+use crate::sfx::{SoundPack, Sounds};
+use crate::silence::Silence;
+use crate::theme::{Theme, ThemePreset};
+use chrono::{DateTime, Utc};
+use fframes::serde::Deserialize;
+use std::fmt::Formatter;
+use std::path::{Path, PathBuf};
+
+/// The manifest shipped with the crate, used where there is no file system to load one from
+/// (e.g. the wasm editor).
+const BUNDLED_STORY: &str = include_str!("../stories/7.toml");
+
+/// Everything a single episode needs: the thread header, the narrated comments in order and the
+/// outro. Loaded from a JSON or TOML manifest and turned into scenes by `UpvotesVideo`.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Story {
+    pub thread: Thread,
+    pub comments: Vec<Comment>,
+    #[serde(default)]
+    pub outro: Outro,
+    #[serde(default)]
+    pub theme: ThemePreset,
+    #[serde(default)]
+    pub locale: Locale,
+    #[serde(default)]
+    pub highlight_style: HighlightStyle,
+    #[serde(default)]
+    pub ducking: Ducking,
+    #[serde(default)]
+    pub mix: Mixes,
+    #[serde(default)]
+    pub loudness: Loudness,
+    #[serde(default)]
+    pub sounds: Sounds,
+    #[serde(default)]
+    pub silence: Silence,
+    /// Moment the video pretends to be recorded at, the ages of the posts are counted up to it.
+    /// Defaults to the latest post or comment so every render of the manifest is the same.
+    pub reference_date: Option<DateTime<Utc>>,
+}
+
+/// Scene opening the video and narrating the thread title.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum Intro {
+    #[default]
+    Bubble,
+    Title,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Thread {
+    pub title: String,
+    pub author: String,
+    pub posted_at: DateTime<Utc>,
+    pub upvotes_count: u64,
+    pub audio_file: String,
+    /// Mix of the title narration instead of the `[mix]` narration.
+    pub mix: Option<Mix>,
+    /// Bubble color of the intro, falls back to the theme one.
+    pub fill: Option<String>,
+    #[serde(default)]
+    pub intro: Intro,
+    #[serde(default = "default_subreddit")]
+    pub subreddit: String,
+    pub subreddit_icon: Option<String>,
+    /// Shown in the comment badge of the title intro.
+    pub comments_count: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "fframes::serde")]
+pub struct Comment {
+    /// Author and avatar are not shown for deleted and removed comments.
+    #[serde(default)]
+    pub author: Author,
+    pub audio_file: String,
+    /// Mix of the narration instead of the `[mix]` narration.
+    pub mix: Option<Mix>,
+    #[serde(default)]
+    pub avatar: String,
+    pub upvote_count: u64,
+    /// Upvotes shown before the upvote click of the comment, defaults to 90% of `upvote_count`.
+    pub upvote_count_from: Option<u64>,
+    pub posted_at: DateTime<Utc>,
+    pub replies_count: u64,
+    #[serde(default)]
+    pub layout: TextLayout,
+    /// Overrides the highlight style of the video for this comment.
+    pub highlight_style: Option<HighlightStyle>,
+    #[serde(default)]
+    pub status: CommentStatus,
+    #[serde(default)]
+    pub awards: Vec<Award>,
+    /// Image or GIF frames posted with the comment.
+    pub attachment: Option<Attachment>,
+    /// Markdown text of the comment shown instead of the narration transcript. It should read
+    /// the same as the narration for the highlight to follow it.
+    pub body: Option<String>,
+    /// Word timings json in the media directory, used instead of the `.vtt` subtitles. The editor
+    /// preview has no media directory to load it from and keeps the subtitles.
+    pub words_file: Option<String>,
+    #[serde(skip)]
+    pub words: Option<PostText>,
+    /// Replies narrated right after the comment, each one may have its own replies.
+    #[serde(default)]
+    pub replies: Vec<Comment>,
+}
+
+impl Comment {
+    pub fn upvote_count_from(&self) -> u64 {
+        self.upvote_count_from
+            .unwrap_or(self.upvote_count - self.upvote_count / 10)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(crate = "fframes::serde")]
+pub struct Outro {
+    /// Bubble color of the outro, falls back to the thread one.
+    pub fill: Option<String>,
+}
+
+fn default_subreddit() -> String {
+    "r/AskReddit".to_owned()
+}
+
+/// Comment the layout can not show, reported when the manifest is loaded.
+#[derive(Debug)]
+pub enum CommentError {
+    TooDeep,
+    /// Visible comments need the `author` and the `avatar` only the hidden ones can leave out.
+    MissingAuthor,
+    MissingAvatar,
+    /// Animated attachment without frames or with a frame rate that never advances them.
+    EmptyAnimation,
+    AnimationFps(f32),
+    /// Award picking an icon past the end of its strip.
+    OutOfStrip(String, IconStrip),
+}
+
+impl std::fmt::Display for CommentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommentError::TooDeep => {
+                write!(f, "replies are nested deeper than {MAX_REPLY_DEPTH} levels")
+            }
+            CommentError::MissingAuthor => write!(f, "visible comments need an author"),
+            CommentError::MissingAvatar => write!(f, "visible comments need an avatar"),
+            CommentError::EmptyAnimation => write!(f, "the attachment animation has no frames"),
+            CommentError::AnimationFps(fps) => {
+                write!(
+                    f,
+                    "the attachment animation fps must be a positive number, got {fps}"
+                )
+            }
+            CommentError::OutOfStrip(icon, strip) => write!(
+                f,
+                "award icon {} is out of the {} icons of {icon}",
+                strip.index, strip.len
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoryError {
+    Io(PathBuf, std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    UnknownFormat(PathBuf),
+    Words(PathBuf, serde_json::Error),
+    /// Comment with the given narration file.
+    Comment(String, CommentError),
+}
+
+impl std::fmt::Display for StoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoryError::Io(path, e) => write!(f, "can not read {}: {e}", path.display()),
+            StoryError::Json(e) => write!(f, "invalid json manifest: {e}"),
+            StoryError::Toml(e) => write!(f, "invalid toml manifest: {e}"),
+            StoryError::UnknownFormat(path) => write!(
+                f,
+                "{} is not a manifest, expected a .json or .toml file",
+                path.display()
+            ),
+            StoryError::Words(path, e) => {
+                write!(f, "invalid word timings in {}: {e}", path.display())
+            }
+            StoryError::Comment(audio_file, e) => write!(f, "comment {audio_file}: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StoryError {}
+
+impl Story {
+    pub fn from_json(source: &str) -> Result<Self, StoryError> {
+        serde_json::from_str::<Self>(source)
+            .map_err(StoryError::Json)?
+            .checked()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, StoryError> {
+        toml::from_str::<Self>(source)
+            .map_err(StoryError::Toml)?
+            .checked()
+    }
+
+    /// Rejects the comments the scenes can not lay out.
+    fn checked(self) -> Result<Self, StoryError> {
+        fn check(comment: &Comment, depth: usize) -> Result<(), StoryError> {
+            let error = |e| StoryError::Comment(comment.audio_file.clone(), e);
+
+            if depth > MAX_REPLY_DEPTH {
+                return Err(error(CommentError::TooDeep));
+            }
+
+            if comment.status.is_visible() {
+                if comment.author.name.is_empty() {
+                    return Err(error(CommentError::MissingAuthor));
+                }
+
+                if comment.avatar.is_empty() {
+                    return Err(error(CommentError::MissingAvatar));
+                }
+            }
+
+            if let Some(AttachmentMedia::Animation { frames, fps }) = comment
+                .attachment
+                .as_ref()
+                .map(|attachment| &attachment.media)
+            {
+                if frames.is_empty() {
+                    return Err(error(CommentError::EmptyAnimation));
+                }
+
+                if *fps <= 0. || !fps.is_finite() {
+                    return Err(error(CommentError::AnimationFps(*fps)));
+                }
+            }
+
+            for award in &comment.awards {
+                if let Some(strip) = award.strip.filter(|strip| strip.index >= strip.len) {
+                    return Err(error(CommentError::OutOfStrip(award.icon.clone(), strip)));
+                }
+            }
+
+            comment
+                .replies
+                .iter()
+                .try_for_each(|reply| check(reply, depth + 1))
+        }
+
+        self.comments
+            .iter()
+            .try_for_each(|comment| check(comment, 0))?;
+
+        Ok(self)
+    }
+
+    /// Reads the manifest picking the format from the file extension.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, StoryError> {
+        let path = path.as_ref();
+        let read = || std::fs::read_to_string(path).map_err(|e| StoryError::Io(path.into(), e));
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json(&read()?),
+            Some("toml") => Self::from_toml(&read()?),
+            _ => Err(StoryError::UnknownFormat(path.into())),
+        }
+    }
+
+    /// Decodes the word timings of every comment that has a `words_file`.
+    pub fn load_words(&mut self, media_dir: impl AsRef<Path>) -> Result<(), StoryError> {
+        fn load(comment: &mut Comment, media_dir: &Path) -> Result<(), StoryError> {
+            if let Some(words_file) = &comment.words_file {
+                let path = media_dir.join(words_file);
+                let source =
+                    std::fs::read_to_string(&path).map_err(|e| StoryError::Io(path.clone(), e))?;
+
+                comment.words =
+                    Some(PostText::decode(&source).map_err(|e| StoryError::Words(path, e))?);
+            }
+
+            comment
+                .replies
+                .iter_mut()
+                .try_for_each(|reply| load(reply, media_dir))
+        }
+
+        self.comments
+            .iter_mut()
+            .try_for_each(|comment| load(comment, media_dir.as_ref()))
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_toml(BUNDLED_STORY).expect("bundled story manifest is invalid")
+    }
+
+    /// Time passed between the post and the reference date.
+    pub fn age(&self, posted_at: DateTime<Utc>) -> chrono::Duration {
+        self.reference_date() - posted_at
+    }
+
+    pub fn reference_date(&self) -> DateTime<Utc> {
+        fn latest(comment: &Comment) -> DateTime<Utc> {
+            comment
+                .replies
+                .iter()
+                .map(latest)
+                .fold(comment.posted_at, DateTime::max)
+        }
+
+        self.reference_date.unwrap_or_else(|| {
+            self.comments
+                .iter()
+                .map(latest)
+                .fold(self.thread.posted_at, DateTime::max)
+        })
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme.theme()
+    }
+
+    /// Sound pack of the theme with the files the manifest replaces.
+    pub fn sound_pack(&self) -> SoundPack<'_> {
+        self.sounds.apply(self.theme().sounds)
+    }
+
+    /// Mixer of the tracks and pads of the manifest, playing the files as they are until
+    /// `mixing::prepare_audio` processes them.
+    pub fn mixer(&self) -> Mixer {
+        Mixer::new(
+            self.mix,
+            self.narration_mixes(),
+            self.loudness,
+            self.silence,
+        )
+    }
+
+    /// Narration files with a mix of their own.
+    pub fn narration_mixes(&self) -> Vec<(String, Mix)> {
+        fn collect(comment: &Comment, mixes: &mut Vec<(String, Mix)>) {
+            if let Some(mix) = comment.mix {
+                mixes.push((comment.audio_file.clone(), mix));
+            }
+
+            for reply in &comment.replies {
+                collect(reply, mixes);
+            }
+        }
+
+        let mut mixes = Vec::new();
+        if let Some(mix) = self.thread.mix {
+            mixes.push((self.thread.audio_file.clone(), mix));
+        }
+
+        for comment in &self.comments {
+            collect(comment, &mut mixes);
+        }
+
+        mixes
+    }
+
+    pub fn thread_fill(&self) -> &str {
+        self.thread
+            .fill
+            .as_deref()
+            .unwrap_or(self.theme().palette.bubble)
+    }
+
+    pub fn outro_fill(&self) -> &str {
+        self.outro.fill.as_deref().unwrap_or(self.thread_fill())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::sfx::SoundPack;
+use fframes::serde::Deserialize;
+
+/// Built in themes, picked with the top level `theme` key of the manifest.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(crate = "fframes::serde", rename_all = "snake_case")]
+pub enum ThemePreset {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ThemePreset {
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemePreset::Light => Theme::LIGHT,
+            ThemePreset::Dark => Theme::DARK,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Solid color under the background image, the whole background of themes without one.
+    pub background: &'static str,
+    /// Bubble of the intro and the outro unless the manifest sets its own.
+    pub bubble: &'static str,
+    /// Thread header card of the title intro.
+    pub card: &'static str,
+    pub text: &'static str,
+    /// Author lines, counters and icons.
+    pub secondary_text: &'static str,
+    /// Timestamps, award counts, quotes and the bodies of deleted comments.
+    pub muted_text: &'static str,
+    /// "more replies" under the comment.
+    pub footer: &'static str,
+    /// Lines joining the avatar of a comment with its body and its replies.
+    pub connector: &'static str,
+    /// Subreddit badge.
+    pub accent: &'static str,
+    pub on_accent: &'static str,
+    /// Badges next to the thread author, moderators and admins.
+    pub op: &'static str,
+    pub moderator: &'static str,
+    pub admin: &'static str,
+    /// Background of user flairs without their own color.
+    pub flair: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fonts {
+    /// Default font of the whole video.
+    pub body: &'static str,
+    /// Comment bodies, usernames and counters.
+    pub medium: &'static str,
+    /// Thread title of the bubble intro.
+    pub heading: &'static str,
+    /// Author line and upvotes of the bubble intro.
+    pub meta: &'static str,
+    /// Inline code of the comment bodies.
+    pub code: &'static str,
+    /// File of the code font in the media directory, only comments with inline code need it.
+    pub code_file: &'static str,
+    /// Color emoji of titles and comments, none of the other fonts has emoji glyphs.
+    pub emoji: &'static str,
+    /// File of the emoji font in the media directory, only texts with emoji need it.
+    pub emoji_file: &'static str,
+}
+
+/// Colors of the karaoke highlight of the narrated comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlight {
+    pub spoken: &'static str,
+    pub current: &'static str,
+    pub upcoming: &'static str,
+}
+
+/// Vote arrows, the upvote of every comment turns from idle to upvoted at the end of its scene.
+/// Hex only, the colors are interpolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Votes {
+    pub upvoted: &'static str,
+    pub idle_fill: &'static str,
+    pub idle_stroke: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub fonts: Fonts,
+    pub highlight: Highlight,
+    pub votes: Votes,
+    /// Full screen image from the media directory drawn over the background color.
+    pub background_image: Option<&'static str>,
+    /// Sound effects of the cues, the manifest can replace them with its `[sounds]` table.
+    pub sounds: SoundPack<'static>,
+}
+
+const FONTS: Fonts = Fonts {
+    body: "Noto Sans",
+    medium: "Noto Sans Medium",
+    heading: "KyivType Serif",
+    meta: "Nunito",
+    code: "JetBrains Mono",
+    code_file: "JetBrainsMono-Regular.ttf",
+    emoji: "Noto Color Emoji",
+    emoji_file: "NotoColorEmoji.ttf",
+};
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        palette: Palette {
+            background: "#dae0e6",
+            bubble: "#fcf8f5",
+            card: "#fff",
+            text: "#000",
+            secondary_text: "#3f3f46",
+            muted_text: "#6b7280",
+            footer: "#747677",
+            connector: "#EDEFF1",
+            accent: "#fe4500",
+            on_accent: "#fff",
+            op: "#0079d3",
+            moderator: "#46a508",
+            admin: "#ff4500",
+            flair: "#edeff1",
+        },
+        fonts: FONTS,
+        highlight: Highlight {
+            spoken: "#000",
+            current: "#dc2626",
+            upcoming: "#4b5563",
+        },
+        votes: Votes {
+            upvoted: "#fe4500",
+            idle_fill: "#fff",
+            idle_stroke: "#888a8c",
+        },
+        background_image: Some("background.png"),
+        sounds: SoundPack::CLASSIC,
+    };
+
+    pub const DARK: Theme = Theme {
+        palette: Palette {
+            background: "#030303",
+            bubble: "#1a1a1b",
+            card: "#1a1a1b",
+            text: "#d7dadc",
+            secondary_text: "#a6a8aa",
+            muted_text: "#818384",
+            footer: "#818384",
+            connector: "#343536",
+            accent: "#fe4500",
+            on_accent: "#fff",
+            op: "#4fbcff",
+            moderator: "#6ecf3c",
+            admin: "#ff585b",
+            flair: "#343536",
+        },
+        fonts: FONTS,
+        highlight: Highlight {
+            spoken: "#d7dadc",
+            current: "#ff585b",
+            upcoming: "#6b6e70",
+        },
+        votes: Votes {
+            upvoted: "#fe4500",
+            idle_fill: "#030303",
+            idle_stroke: "#818384",
+        },
+        background_image: None,
+        sounds: SoundPack::CLASSIC,
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::LIGHT
+    }
+}
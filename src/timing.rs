@@ -0,0 +1,147 @@
+use crate::shared::{seconds_to_frames, FPS};
+
+/// Length of a scene and its overlap with the next one. Kept apart from `Scene::duration` so the
+/// timeline of the video can be laid out without the renderer, e.g. to mix the audio ahead of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing<'a> {
+    /// Narration played from the start of the scene, the scene lasts at least as long.
+    pub narration: Option<&'a str>,
+    /// Seconds the scene runs past the end of its narration, its whole length without one.
+    pub tail: f32,
+    /// Seconds the next scene starts before this one ends.
+    pub overlap: f32,
+}
+
+impl<'a> Timing<'a> {
+    pub fn duration(self) -> fframes::Duration<'a> {
+        match self.narration {
+            Some(narration) => {
+                fframes::Duration::FromAudio(narration) + fframes::Duration::Seconds(self.tail)
+            }
+            None => fframes::Duration::Seconds(self.tail),
+        }
+    }
+
+    pub fn overlap(self) -> fframes::Overlap {
+        fframes::Overlap::Next(self.overlap)
+    }
+}
+
+pub trait SceneTiming {
+    fn timing(&self) -> Timing<'_>;
+}
+
+/// Start of every scene and of the narration it plays, in seconds from the start of the video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneSpan<'a> {
+    pub start: f32,
+    pub end: f32,
+    /// Narration with the second it stops at.
+    pub narration: Option<(&'a str, f32)>,
+}
+
+/// Lays the scenes out one after another, `narration_length` gives the length of a narration
+/// file in seconds. Every length is rounded to whole frames like the renderer does, so the spans
+/// stay on the frame the scenes start at however long the video gets.
+pub fn layout_scenes<'a, E>(
+    timings: impl IntoIterator<Item = Timing<'a>>,
+    mut narration_length: impl FnMut(&'a str) -> Result<f32, E>,
+) -> Result<Vec<SceneSpan<'a>>, E> {
+    let second = |frame: usize| frame as f32 / FPS as f32;
+    let mut start = 0;
+    let mut spans = Vec::new();
+
+    for timing in timings {
+        let narration = match timing.narration {
+            Some(file) => Some((file, start + seconds_to_frames(narration_length(file)?))),
+            None => None,
+        };
+        let end = narration.map_or(start, |(_, end)| end) + seconds_to_frames(timing.tail);
+
+        spans.push(SceneSpan {
+            start: second(start),
+            end: second(end),
+            narration: narration.map(|(file, end)| (file, second(end))),
+        });
+        start = end.saturating_sub(seconds_to_frames(timing.overlap));
+    }
+
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn timing(narration: Option<&str>, tail: f32, overlap: f32) -> Timing<'_> {
+        Timing {
+            narration,
+            tail,
+            overlap,
+        }
+    }
+
+    #[test]
+    fn snaps_every_length_to_whole_frames() {
+        let spans = layout_scenes(
+            [
+                timing(Some("title.mp3"), 0.5, 0.25),
+                timing(Some("comment.mp3"), 0., 0.),
+                timing(None, 0.3, 0.),
+            ],
+            |narration| {
+                Ok::<_, Infallible>(match narration {
+                    // 60.24 frames, the renderer plays 60 of them
+                    "title.mp3" => 1.004,
+                    _ => 2.,
+                })
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            spans,
+            vec![
+                SceneSpan {
+                    start: 0.,
+                    end: 1.5,
+                    narration: Some(("title.mp3", 1.)),
+                },
+                SceneSpan {
+                    start: 1.25,
+                    end: 3.25,
+                    narration: Some(("comment.mp3", 3.25)),
+                },
+                SceneSpan {
+                    start: 3.25,
+                    end: 3.55,
+                    narration: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rounding_does_not_drift_over_long_videos() {
+        // a third of a second is 20 frames, summing the seconds would drift off them
+        let spans = layout_scenes(
+            std::iter::repeat_n(timing(Some("clip.mp3"), 0., 0.), 100),
+            |_| Ok::<_, Infallible>(1. / 3.),
+        )
+        .unwrap();
+
+        assert_eq!(spans[99].start, (99 * 20) as f32 / FPS as f32);
+        assert_eq!(spans[99].end, 2000. / FPS as f32);
+    }
+
+    #[test]
+    fn overlaps_never_start_before_the_video() {
+        let spans = layout_scenes([timing(None, 0.1, 0.5), timing(None, 1., 0.)], |_| {
+            Ok::<_, Infallible>(0.)
+        })
+        .unwrap();
+
+        assert_eq!(spans[1].start, 0.);
+    }
+}
@@ -1,30 +1,65 @@
-use fframes::{BreakLinesOpts, Easing, Scene};
-
-use crate::shared::{upvote_arrow, TransitionTimelines};
-
+use fframes::{BreakLinesOpts, Scene, Svgr};
+
+use crate::assets::{Asset, SceneAssets};
+use crate::emoji;
+use crate::format::Canvas;
+use crate::locale::{Locale, TimeStyle};
+use crate::mixing::Mixer;
+use crate::sfx::{cue_assets, cue_tracks, CueAt, SceneCues, SoundPack};
+use crate::shared::{upvote_arrow, TransitionTimelines, EXIT_DURATION};
+use crate::theme::Theme;
+use crate::timing::{SceneTiming, Timing};
+
+/// Thread header card, an alternative to the bubble intro looking like the reddit post itself.
 #[derive(Debug)]
-pub struct TitleScene {
-    pub title: String,
-    pub posted_by: String,
-    pub when: String,
-    pub upvote_count: String,
-    pub comment_count: String,
+pub struct TitleScene<'a> {
+    pub title: &'a str,
+    pub subreddit: &'a str,
+    /// Rendered as a generic subreddit badge when missing.
+    pub subreddit_icon: Option<&'a str>,
+    pub posted_by: &'a str,
+    /// Age of the thread at the reference date of the video.
+    pub posted_ago: chrono::Duration,
+    pub upvote_count: u64,
+    /// The comment badge is left out when missing.
+    pub comment_count: Option<u64>,
+    pub audio_file: &'a str,
     pub timelines: TransitionTimelines,
+    pub canvas: Canvas,
+    pub theme: Theme,
+    pub locale: Locale,
+    pub mixer: &'a Mixer,
+    pub sounds: SoundPack<'a>,
 }
 
-impl Scene for TitleScene {
+const X_PADDING: usize = 220;
+const Y_PADDING: usize = 400;
+
+impl Scene for TitleScene<'_> {
     fn duration(&self) -> fframes::video::Duration {
-        fframes::video::Duration::FromAudio("title.mp3") + fframes::video::Duration::Seconds(0.4)
+        self.timing().duration()
+    }
+
+    fn overlap(&self) -> fframes::Overlap {
+        self.timing().overlap()
     }
 
-    fn audio_map(&self, _scene_info: &fframes::SceneInfo) -> fframes::audio_map::AudioMap {
-        fframes::AudioMap::from([(
-            "title.mp3",
+    fn audio_map(&self, scene_info: &fframes::SceneInfo) -> fframes::audio_map::AudioMap {
+        let narration = (
+            self.mixer.narration(self.audio_file),
             (
                 fframes::AudioTimestamp::Second(0),
                 fframes::AudioTimestamp::Eof,
             ),
-        )])
+        );
+        let cues = self.cues();
+
+        fframes::AudioMap::from_iter(std::iter::once(narration).chain(cue_tracks(
+            &cues,
+            &self.sounds,
+            self.mixer,
+            scene_info,
+        )))
     }
 
     fn render_frame(
@@ -33,7 +68,8 @@ impl Scene for TitleScene {
         ctx: &fframes::FFramesContext,
     ) -> fframes::Svgr {
         let scene_info = ctx.get_scene_info(self).unwrap();
-        let (translate_timeline, skew_timeline) = self.timelines.get(&frame, &scene_info);
+        let (translate_timeline, skew_timeline) =
+            self.timelines.get(&frame, scene_info, &self.canvas);
 
         let translate_x = frame.animate(translate_timeline);
         let skew_x = skew_timeline
@@ -41,118 +77,164 @@ impl Scene for TitleScene {
             .map(|tl| frame.animate(tl))
             .unwrap_or(0.);
 
-        const X_PADDING: usize = 220;
-        const Y_PADDING: usize = 400;
+        let Theme { palette, fonts, .. } = self.theme;
+        let x = X_PADDING.to_string();
+        let y = (Y_PADDING + 200).to_string();
+        let title_opts = BreakLinesOpts {
+            width: 780,
+            font_family: fonts.medium,
+            font_size: 68,
+            x: &x,
+            y: &y,
+            align: fframes::TextAlign::Left,
+            font_weight: 500,
+            fill: palette.text,
+            line_height: 1.3,
+            ..Default::default()
+        };
+
+        let title_structure =
+            emoji::break_lines(&frame, ctx, self.title, &title_opts).unwrap_or_default();
+
+        let comments_y = Y_PADDING + 170 + title_structure.occupied_height();
+
+        let card_top = Y_PADDING - 100;
+        let offset_x = translate_x + self.canvas.column_x() as f32;
+        let offset_y = self.canvas.scale_y(card_top) as f32 - card_top as f32;
+
+        let subreddit_icon = match self.subreddit_icon {
+            Some(icon) => fframes::svgr!(
+                <image
+                    y={Y_PADDING + 20}
+                    x={X_PADDING - 10}
+                    width="90"
+                    height="90"
+                    href={ctx.get_image_link(icon)}
+                />
+            ),
+            None => fframes::svgr!(
+                <circle cx={X_PADDING + 35} cy={Y_PADDING + 65} r="45" fill={palette.accent} />
+                <text
+                    x={X_PADDING + 35}
+                    y={Y_PADDING + 68}
+                    dominant-baseline="middle"
+                    text-anchor="middle"
+                    font-family={fonts.medium}
+                    font-size="44"
+                    fill={palette.on_accent}
+                >
+                    "r/"
+                </text>
+            ),
+        };
+
+        let comment_count = match self.comment_count {
+            None => Svgr::default(),
+            Some(comment_count) => fframes::svgr!(
+                <svg
+                    x={X_PADDING - 10}
+                    y={comments_y}
+                    width="80"
+                    height="80"
+                    viewBox="0 0 400 400"
+                    fill="none"
+                    xmlns="http://www.w3.org/2000/svg"
+                >
+                    <path
+                        d="M136.944 292.438L132.526 329.847C131.727 336.615 139.214 341.219 144.893 337.452L181 313.5L218.355 285.129C219.747 284.072 221.446 283.5 223.194 283.5H311C328.673 283.5 343 269.173 343 251.5V167V86C343 68.3269 328.673 54 311 54H199.5H88C70.3269 54 56 68.3269 56 86V251.5C56 269.173 70.3269 283.5 88 283.5H101.5H129C133.79 283.5 137.506 287.681 136.944 292.438Z"
+                        stroke={palette.secondary_text}
+                        stroke-width="24"
+                    />
+                </svg>
+
+                <text x={X_PADDING + 80} y={comments_y + 55} font-size="44" font-weight="medium" fill={palette.secondary_text} font-family={fonts.medium}>
+                    {self.locale.compact_count(comment_count)}
+                    " comments"
+                </text>
+            ),
+        };
 
         fframes::svgr!(
-        //   <g transform={format!("translate({translate_x}, 0) skewX({skew_x})")}>
-            // <rect
-            //   fill="#FBAE3C"
-            //   x="0"
-            //   y="0"
-            //   width="1080"
-            //   height="1920"
-            //   id="bubble"
-            // />
-
-            // <clipPath id="containerClip">
-            //    <use href="#bubble" />
-            // </clipPath>
-
-            // <g clip-path="url(#containerClip)">
-            //     <image
-            //         y="20%"
-            //         x={1080 / 2 - 600 / 2}
-            //         width="600"
-            //         height="600"
-            //         href={ctx.get_image_link("test.png")}
-            //     />
-
-            //     <text
-            //        x="50%"
-            //        y="55%"
-            //        dominant-baseline="middle"
-            //        text-anchor="middle"
-            //        font-family="Noto Sans Medium"
-            //        font-size="60"
-            //        fill="#3f3f46"
-            //     >
-            //        "r/AskReddit"
-            //     </text>
-            //     <text
-            //        x="50%"
-            //        y="62%"
-            //        dominant-baseline="middle"
-            //        text-anchor="middle"
-            //        font-family="KyivType Serif"
-            //        font-weight="black"
-            //        font-size="160"
-            //     >
-            //        "Subscribe"
-            //     </text>
-            // </g>
-
-            // <g fill="none" stroke="#3f3f46">
-            //   {upvote_arrow(100, Y_PADDING + 155, 0)}
-            //   {upvote_arrow(100, Y_PADDING + 300, 180)}
-            // </g>
-
-            // <text x="90" y={Y_PADDING + 280} font-size="40" font-weight="medium" fill="black" font-family="Noto Sans Medium">
-            //     {self.upvote_count.as_str()}
-            // </text>
-
-            // <text y={Y_PADDING} x={X_PADDING} fill="#3f3f46" font-size="40">
-            //   {format!("Posted by {} {}", self.posted_by, self.when)}
-            // </text>
-
-            // <image
-            //     y={Y_PADDING + 20}
-            //     x={X_PADDING - 10}
-            //     width="90"
-            //     height="90"
-            //     href={ctx.get_image_link("brand.png")}
-            // />
-
-            // {
-            //     frame.text_break_lines(
-            //     ctx,
-            //         self.title.as_str(),
-            //         &BreakLinesOpts {
-            //             width: 780,
-            //             font_family: "Noto Sans Medium",
-            //             font_size: 68,
-            //             x: &X_PADDING.to_string(),
-            //             y: &(Y_PADDING + 200).to_string(),
-            //             align: fframes::TextAlign::Left,
-            //             font_weight: 500,
-            //             fill: "#000",
-            //             line_height: 1.3,
-            //             ..Default::default()
-            //         },
-            //     ).unwrap_or_default()
-            // }
-
-            // <svg
-            //     x={X_PADDING - 10}
-            //     y={Y_PADDING + 510}
-            //     width="80"
-            //     height="80"
-            //     viewBox="0 0 400 400"
-            //     fill="none"
-            //     xmlns="http://www.w3.org/2000/svg"
-            // >
-            //     <path
-            //         d="M136.944 292.438L132.526 329.847C131.727 336.615 139.214 341.219 144.893 337.452L181 313.5L218.355 285.129C219.747 284.072 221.446 283.5 223.194 283.5H311C328.673 283.5 343 269.173 343 251.5V167V86C343 68.3269 328.673 54 311 54H199.5H88C70.3269 54 56 68.3269 56 86V251.5C56 269.173 70.3269 283.5 88 283.5H101.5H129C133.79 283.5 137.506 287.681 136.944 292.438Z"
-            //         stroke="#3f3f46"
-            //         stroke-width="24"
-            //     />
-            // </svg>
-
-            // <text x="290" y={Y_PADDING + 565} font-size="44" font-weight="medium" fill="#3f3f46" font-family="Noto Sans Medium">
-            //     {self.comment_count.as_str()}
-            //     " comments"
-            // </text>
-        //   </g>
+            <g transform={format!("translate({offset_x}, {offset_y}) skewX({skew_x})")}>
+                <rect
+                    x="60"
+                    y={card_top}
+                    width="960"
+                    height={comments_y + 180 - Y_PADDING}
+                    rx="40"
+                    fill={palette.card}
+                />
+
+                <g fill="none" stroke={palette.secondary_text}>
+                    {upvote_arrow(100, Y_PADDING + 155, 0)}
+                    {upvote_arrow(100, Y_PADDING + 300, 180)}
+                </g>
+
+                <text x="135" y={Y_PADDING + 280} text-anchor="middle" font-size="40" font-weight="medium" fill={palette.text} font-family={fonts.medium}>
+                    {self.locale.compact_count(self.upvote_count)}
+                </text>
+
+                <text y={Y_PADDING} x={X_PADDING} fill={palette.secondary_text} font-size="40">
+                    {format!(
+                        "Posted by {} {}",
+                        self.posted_by,
+                        self.locale.relative_time(self.posted_ago, TimeStyle::Long)
+                    )}
+                </text>
+
+                {subreddit_icon}
+
+                <text
+                    x={X_PADDING + 100}
+                    y={Y_PADDING + 68}
+                    dominant-baseline="middle"
+                    font-family={fonts.medium}
+                    font-size="52"
+                    fill={palette.text}
+                >
+                    {self.subreddit}
+                </text>
+
+                {emoji::render_lines(&title_structure, &title_opts, self.title, fonts.emoji)}
+
+                {comment_count}
+            </g>
         )
     }
 }
+
+impl SceneTiming for TitleScene<'_> {
+    fn timing(&self) -> Timing<'_> {
+        Timing {
+            narration: Some(self.mixer.narration(self.audio_file)),
+            tail: self.mixer.silence.pad,
+            overlap: EXIT_DURATION,
+        }
+    }
+}
+
+impl SceneCues for TitleScene<'_> {
+    fn cues(&self) -> Vec<CueAt<'_>> {
+        self.timelines.exit_cue().into_iter().collect()
+    }
+}
+
+impl SceneAssets for TitleScene<'_> {
+    fn assets(&self) -> Vec<Asset> {
+        let mut assets = vec![Asset::track(
+            self.audio_file,
+            self.mixer.narration_mix(self.audio_file),
+        )];
+        assets.extend(cue_assets(&self.cues(), &self.sounds, self.mixer));
+
+        if let Some(icon) = self.subreddit_icon {
+            assets.push(Asset::image(icon));
+        }
+
+        if emoji::contains_emoji(self.title) {
+            assets.push(Asset::font(self.theme.fonts.emoji_file));
+        }
+
+        assets
+    }
+}
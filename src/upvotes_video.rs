@@ -1,109 +1,229 @@
+use crate::assets::{Asset, StoryScene};
 use crate::bubble::BubbleTransitionScene;
-use crate::post::{Post, PostText};
-use crate::shared::TransitionTimelines;
+use crate::format::{Canvas, Format, Portrait};
+use crate::mixing::Mixer;
+use crate::post::{ParentComment, Post};
+use crate::shared::{TransitionTimelines, FPS};
+use crate::story::{Comment, Intro, Story};
+use crate::title::TitleScene;
 pub use fframes::{audio_data, fframes_context, frame, video::Video};
 use fframes::{AudioMap, AudioTimestamp, Scene, Svgr};
+use std::marker::PhantomData;
 
+pub const BACKGROUND_MUSIC: &str = "background2.mp3";
+
+/// Orders a comment and all of its nested replies the way they are narrated, depth first,
+/// together with the chain of comments each of them replies to.
+fn flatten_thread<'a>(
+    comment: &'a Comment,
+    parents: &[&'a Comment],
+    thread: &mut Vec<(&'a Comment, Vec<&'a Comment>)>,
+) {
+    thread.push((comment, parents.to_vec()));
+
+    let parents = [parents, &[comment]].concat();
+    for reply in &comment.replies {
+        flatten_thread(reply, &parents, thread);
+    }
+}
+
+/// The story rendered on the canvas of the given format, portrait unless specified.
 #[derive(Debug)]
-pub struct UpvotesVideo {}
+pub struct UpvotesVideo<F: Format = Portrait> {
+    pub story: Story,
+    pub format: PhantomData<F>,
+    /// Files played for the tracks of the story, see `mixing::prepare_audio`.
+    pub mixer: Mixer,
+}
+
+impl<F: Format> UpvotesVideo<F> {
+    pub fn new(story: Story) -> Self {
+        Self {
+            mixer: story.mixer(),
+            story,
+            format: PhantomData,
+        }
+    }
+
+    pub fn scenes(&self) -> Vec<Box<dyn StoryScene + '_>> {
+        let thread = &self.story.thread;
+        let canvas = Canvas::of::<F>();
+        let theme = self.story.theme();
+        let locale = self.story.locale;
+        let sounds = self.story.sound_pack();
 
-impl Video for UpvotesVideo {
-    const FPS: usize = 60;
-    const WIDTH: usize = 1080;
-    const HEIGHT: usize = 1920;
+        let mut vec: Vec<Box<dyn StoryScene + '_>> = vec![match thread.intro {
+            Intro::Bubble => Box::new(BubbleTransitionScene {
+                title: &thread.title,
+                fill: self.story.thread_fill(),
+                upvotes_count: thread.upvotes_count,
+                author: &thread.author,
+                posted_ago: self.story.age(thread.posted_at),
+                variant: crate::bubble::Variant::Title {
+                    audio_file: &thread.audio_file,
+                },
+                canvas,
+                theme,
+                locale,
+                mixer: &self.mixer,
+                sounds,
+            }),
+            Intro::Title => Box::new(TitleScene {
+                title: &thread.title,
+                subreddit: &thread.subreddit,
+                subreddit_icon: thread.subreddit_icon.as_deref(),
+                posted_by: &thread.author,
+                posted_ago: self.story.age(thread.posted_at),
+                upvote_count: thread.upvotes_count,
+                comment_count: thread.comments_count,
+                audio_file: &thread.audio_file,
+                timelines: TransitionTimelines::new(false, true),
+                canvas,
+                theme,
+                locale,
+                mixer: &self.mixer,
+                sounds,
+            }),
+        }];
+
+        for (index, comment) in self.story.comments.iter().enumerate() {
+            let covered_by_bubble = index == self.story.comments.len() - 1;
+            let mut chain = Vec::new();
+            flatten_thread(comment, &[], &mut chain);
+
+            let last = chain.len() - 1;
+            let revealed_by_bubble = thread.intro == Intro::Bubble && vec.len() == 1;
+            vec.extend(
+                chain
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, (comment, parents))| {
+                        let timelines = TransitionTimelines::new(position == 0, position == last);
+                        let timelines = if covered_by_bubble && position == last {
+                            timelines.covered_by_bubble()
+                        } else {
+                            timelines
+                        };
+
+                        Box::new(Post {
+                            author: &comment.author,
+                            audio_file: &comment.audio_file,
+                            avatar: &comment.avatar,
+                            timelines: if revealed_by_bubble && position == 0 {
+                                timelines.revealed_by_bubble()
+                            } else {
+                                timelines
+                            },
+                            upvote_count: comment.upvote_count,
+                            upvote_count_from: comment.upvote_count_from(),
+                            posted_ago: self.story.age(comment.posted_at),
+                            replies_count: comment.replies_count,
+                            layout: comment.layout,
+                            highlight_style: comment
+                                .highlight_style
+                                .unwrap_or(self.story.highlight_style),
+                            body: comment.body.as_deref(),
+                            status: comment.status,
+                            awards: &comment.awards,
+                            attachment: comment.attachment.as_ref(),
+                            words: comment.words.as_ref(),
+                            canvas,
+                            theme,
+                            locale,
+                            mixer: &self.mixer,
+                            sounds,
+                            parents: parents
+                                .into_iter()
+                                .map(|parent| ParentComment {
+                                    author: &parent.author,
+                                    avatar: &parent.avatar,
+                                    posted_ago: self.story.age(parent.posted_at),
+                                    upvote_count: parent.upvote_count,
+                                    status: parent.status,
+                                })
+                                .collect(),
+                        }) as Box<dyn StoryScene + '_>
+                    }),
+            );
+        }
+
+        vec.push(Box::new(BubbleTransitionScene {
+            title: &thread.title,
+            upvotes_count: thread.upvotes_count,
+            fill: self.story.outro_fill(),
+            author: &thread.author,
+            posted_ago: self.story.age(thread.posted_at),
+            variant: crate::bubble::Variant::Exit,
+            canvas,
+            theme,
+            mixer: &self.mixer,
+            sounds,
+            locale,
+        }));
+
+        vec
+    }
+
+    /// Assets requested by the video itself rather than by any of the scenes.
+    pub fn assets(&self) -> Vec<Asset> {
+        let mut assets = vec![Asset::track(BACKGROUND_MUSIC, self.mixer.mixes.music)];
+
+        if let Some(background_image) = self.story.theme().background_image {
+            assets.push(Asset::image(background_image));
+        }
+
+        assets
+    }
+}
+
+impl<F: Format> Video for UpvotesVideo<F> {
+    const FPS: usize = FPS;
+    const WIDTH: usize = F::WIDTH;
+    const HEIGHT: usize = F::HEIGHT;
 
     fn audio(&self) -> AudioMap {
         AudioMap::from([(
-            "background2.mp3",
+            self.mixer.music(),
             (AudioTimestamp::Second(0), AudioTimestamp::Eof),
         )])
     }
 
     fn define_scenes(&self) -> fframes::Scenes {
-        let primary_color = "#fcf8f5";
-        let title = r#"What yells I have no life?"#;
-        let upvotes_count = "24.8k";
-        let author = "u/11pxny4";
-        let when = "2 days ago";
-
-        let vec: Vec<Box<dyn Scene>> = vec![
-            Box::new(BubbleTransitionScene {
-                title,
-                fill: primary_color,
-                upvotes_count,
-                author,
-                when,
-                variant: crate::bubble::Variant::Title {
-                    audio_file: "7-001.mp3",
-                },
-            }),
-            Box::new(Post {
-                title: "purringfox",
-                audio_file: "7-002.mp3",
-                avatar: "avatar_1.png",
-                timelines: TransitionTimelines::init(),
-                upvote_count: "4.3k",
-                posted_when: "2 d. ago",
-                replies_count: "158",
-            }),
-            Box::new(Post {
-                title: "Elin-Calliel",
-                audio_file: "7-006.mp3",
-                avatar: "avatar_6.png",
-                timelines: TransitionTimelines::init(),
-                upvote_count: "20.0k",
-                posted_when: "2 d. ago",
-                replies_count: "82",
-            }),
-            Box::new(Post {
-                title: "nowhereman531",
-                audio_file: "7-003.mp3",
-                avatar: "avatar_2.png",
-                timelines: TransitionTimelines::init(),
-                upvote_count: "12.9k",
-                posted_when: "2 d. ago",
-                replies_count: "116",
-            }),
-            Box::new(Post {
-                title: "Portarossa",
-                audio_file: "7-004.mp3",
-                avatar: "avatar_3.png",
-                timelines: TransitionTimelines::init(),
-                upvote_count: "13.5k",
-                posted_when: "2 d. ago",
-                replies_count: "204",
-            }),
-            Box::new(Post {
-                title: "hello_friend",
-                audio_file: "7-005.mp3",
-                avatar: "avatar_4.png",
-                timelines: TransitionTimelines::init(),
-                upvote_count: "10.1k",
-                posted_when: "2 d. ago",
-                replies_count: "80",
-            }),
-            Box::new(BubbleTransitionScene {
-                title,
-                upvotes_count,
-                fill: primary_color,
-                author,
-                when,
-                variant: crate::bubble::Variant::Exit,
-            }),
-        ];
+        let vec = self
+            .scenes()
+            .into_iter()
+            .map(|scene| scene as Box<dyn Scene + '_>)
+            .collect::<Vec<_>>();
 
         fframes::Scenes::from(vec)
     }
 
     fn render_frame(&self, frame: frame::Frame, ctx: &fframes_context::FFramesContext) -> Svgr {
+        let theme = self.story.theme();
+        let background_image = match theme.background_image {
+            Some(image) => fframes::svgr!(
+                <image
+                    href={ctx.get_image_link(image)}
+                    width={Self::WIDTH}
+                    height={Self::HEIGHT}
+                    x="0"
+                    y="0"
+                    preserveAspectRatio="xMidYMid slice"
+                />
+            ),
+            None => Svgr::default(),
+        };
+
         fframes::svgr!(
            <svg
             xmlns="http://www.w3.org/2000/svg"
             width={Self::WIDTH}
             height={Self::HEIGHT}
-            font-family="Noto Sans"
+            font-family={theme.fonts.body}
+            fill={theme.palette.text}
           >
-            <image href={ctx.get_image_link("background.png")} width={1080} height={1920} x="0" y="0" />
+            <rect width={Self::WIDTH} height={Self::HEIGHT} fill={theme.palette.background} />
+            {background_image}
             {ctx.render_scenes(&frame)}
           </svg>
         )